@@ -1,12 +1,160 @@
-use digest::DynDigest;
+use data_encoding::{BASE32, BASE64, BASE64URL_NOPAD, HEXUPPER};
+use digest::{core_api::BlockSizeUser, Digest, DynDigest};
+use hmac::{Mac, SimpleHmac};
+
+// Shared with the plugin, so examples are encoded the same way as outputs
+#[allow(dead_code)]
+#[path = "src/nix32.rs"]
+mod nix32;
 
 const TEST_TEXT: &str = "abcdefghijklmnopqrstuvwxyz";
 
+// RFC 4231 test cases 1 and 2, also used by RFC 2104
+const HMAC_TEST_BINARY_KEY: [u8; 20] = [0x0b; 20];
+const HMAC_TEST_BINARY_KEY_TEXT: &str = "Hi There";
+const HMAC_TEST_KEY: &str = "Jefe";
+const HMAC_TEST_KEY_TEXT: &str = "what do ya want for nothing?";
+
+// The same test cases as Nushell literals, used by published test vectors
+#[cfg(any(
+  feature = "blake2",
+  feature = "ripemd",
+  feature = "sha1",
+  feature = "sha2",
+  feature = "sha3",
+  feature = "sm3",
+))]
+const CASE_1_KEY: &str = "0x[0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b]";
+#[cfg(any(
+  feature = "blake2",
+  feature = "ripemd",
+  feature = "sha1",
+  feature = "sha2",
+  feature = "sha3",
+  feature = "sm3",
+))]
+const CASE_1_DATA: &str = "'Hi There'";
+#[cfg(any(
+  feature = "blake2",
+  feature = "ripemd",
+  feature = "sha1",
+  feature = "sha2",
+  feature = "sha3",
+  feature = "sm3",
+))]
+const CASE_2_KEY: &str = "'Jefe'";
+#[cfg(any(
+  feature = "blake2",
+  feature = "ripemd",
+  feature = "sha1",
+  feature = "sha2",
+  feature = "sha3",
+  feature = "sm3",
+))]
+const CASE_2_DATA: &str = "'what do ya want for nothing?'";
+// RFC 7836 test case for HMAC-Streebog
+#[cfg(feature = "streebog")]
+const RFC_7836_KEY: &str =
+  "0x[000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f]";
+#[cfg(feature = "streebog")]
+const RFC_7836_DATA: &str = "0x[0126bdb87800af214341456563780100]";
+
+type HmacFn = fn(&[u8], &[u8]) -> Vec<u8>;
+type EncodeFn = fn(&[u8]) -> String;
+
+// Names of encodings of `--encoding`, their descriptions in examples and
+// encoders. Hex and binary outputs have examples of their own
+const ENCODINGS: &[(&str, &str, EncodeFn)] = &[
+  ("hex-upper", "uppercase hex-encoded", |b| HEXUPPER.encode(b)),
+  ("base32", "base32-encoded", |b| BASE32.encode(b)),
+  ("nix32", "encoded with Nix base32", nix32::encode),
+  ("base58", "base58-encoded", |b| {
+    bs58::encode(b).into_string()
+  }),
+  ("base64", "base64-encoded", |b| BASE64.encode(b)),
+  ("base64url", "base64url-encoded", |b| {
+    BASE64URL_NOPAD.encode(b)
+  }),
+];
+
 struct GeneratedHasherImplMeta {
   crate_name: &'static str,
   hasher_type_name: &'static str,
   hasher_command: &'static str,
   hasher: Box<dyn DynDigest>,
+  hmac: Option<HmacFn>,
+  // Path of a function, that creates a hasher with a keyed mode of the
+  // algorithm's own. Other algorithms with `hmac` are keyed with HMAC
+  keyed: Option<&'static str>,
+  // Published HMAC test vectors. Algorithms without any get examples computed
+  // with `hmac`, which are regression outputs rather than known answers: they
+  // only check that the plugin agrees with this build script
+  hmac_vectors: &'static [HmacVector],
+  // The commands of extendable-output functions are implemented in xof.rs
+  xof: bool,
+  security: Security,
+  // Code of the algorithm in the multicodec table, used by multihashes
+  multicodec: Option<u64>,
+}
+
+// Algorithms with hand-written commands, that take their parameters at
+// runtime, so they have no registry entries. `hash algorithms` lists them with
+// the sizes and codes of their default parameters
+struct CommandAlgorithmMeta {
+  crate_name: &'static str,
+  type_name: &'static str,
+  command: &'static str,
+  output_size: usize,
+  block_size: usize,
+  keyed: bool,
+  xof: bool,
+  security: Security,
+  multicodec: Option<u64>,
+}
+
+struct HmacVector {
+  // Where the vector is published
+  source: &'static str,
+  // Key and data as Nushell literals
+  key: &'static str,
+  data: &'static str,
+  // Hex-encoded MAC
+  mac: &'static str,
+}
+
+struct Pbkdf2Vector {
+  // Where the vector is published
+  source: &'static str,
+  // Command of the algorithm of HMAC
+  algorithm: &'static str,
+  // Password and salt as Nushell literals
+  password: &'static str,
+  salt: &'static str,
+  iterations: u32,
+  // Key length, if it's not the output size of the algorithm
+  length: Option<usize>,
+  // Hex-encoded key
+  key: &'static str,
+}
+
+// Some variants are unused, depending on enabled features
+#[allow(dead_code)]
+#[derive(Debug)]
+enum Security {
+  // Practical collision attacks are known
+  Broken,
+  // Not broken, but obsolete or with a small security margin
+  Legacy,
+  Ok,
+}
+
+fn hmac<D: Digest + BlockSizeUser>(key: &[u8], data: &[u8]) -> Vec<u8> {
+  SimpleHmac::<D>::new_from_slice(key)
+    .expect("HMAC accepts keys of any length")
+    .chain_update(data)
+    .finalize()
+    .into_bytes()
+    .to_vec()
 }
 
 #[cfg(any(
@@ -42,6 +190,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "AsconHash",
       hasher_command: "ascon",
       hasher: Box::new(ascon_hash::AsconHash::default()),
+      hmac: Some(hmac::<ascon_hash::AsconHash>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "ascon-hash")]
     GeneratedHasherImplMeta {
@@ -49,6 +203,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "AsconAHash",
       hasher_command: "ascon-a",
       hasher: Box::new(ascon_hash::AsconAHash::default()),
+      hmac: Some(hmac::<ascon_hash::AsconAHash>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "belt-hash")]
     GeneratedHasherImplMeta {
@@ -56,6 +216,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "BeltHash",
       hasher_command: "belt",
       hasher: Box::new(belt_hash::BeltHash::default()),
+      hmac: Some(hmac::<belt_hash::BeltHash>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "blake2")]
     GeneratedHasherImplMeta {
@@ -63,6 +229,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Blake2s256",
       hasher_command: "blake2s-256",
       hasher: Box::new(blake2::Blake2s256::default()),
+      hmac: Some(hmac::<blake2::Blake2s256>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac:
+            "65a8b7c5cc9136d424e82c37e2707e74e913c0655b99c75f40edf387453a3260",
+        },
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac:
+            "90b6281e2f3038c9056af0b4a7e763cae6fe5d9eb4386a0ec95237890c104ff0",
+        },
+      ],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0xb260),
     },
     #[cfg(feature = "blake2")]
     GeneratedHasherImplMeta {
@@ -70,6 +257,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Blake2b512",
       hasher_command: "blake2b-512",
       hasher: Box::new(blake2::Blake2b512::default()),
+      hmac: Some(hmac::<blake2::Blake2b512>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac:
+            "358a6a184924894fc34bee5680eedf57d84a37bb38832f288e3b27dc63a98cc8\
+           c91e76da476b508bc6b2d408a248857452906e4a20b48c6b4b55d2df0fe1dd24",
+        },
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac:
+            "6ff884f8ddc2a6586b3c98a4cd6ebdf14ec10204b6710073eb5865ade37a2643\
+           b8807c1335d107ecdb9ffeaeb6828c4625ba172c66379efcd222c2de11727ab4",
+        },
+      ],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0xb240),
     },
     #[cfg(feature = "blake3")]
     GeneratedHasherImplMeta {
@@ -77,6 +287,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Hasher",
       hasher_command: "blake3",
       hasher: Box::new(blake3::Hasher::new()),
+      // BLAKE3 is an XOF with a keyed mode of its own, HMAC isn't defined for
+      // it
+      hmac: None,
+      keyed: Some("crate::xof::new_keyed_blake3"),
+      hmac_vectors: &[],
+      xof: true,
+      security: Security::Ok,
+      multicodec: Some(0x1e),
     },
     #[cfg(feature = "fsb")]
     GeneratedHasherImplMeta {
@@ -84,6 +302,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Fsb160",
       hasher_command: "fsb160",
       hasher: Box::new(fsb::Fsb160::default()),
+      hmac: Some(hmac::<fsb::Fsb160>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "fsb")]
     GeneratedHasherImplMeta {
@@ -91,6 +315,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Fsb224",
       hasher_command: "fsb224",
       hasher: Box::new(fsb::Fsb224::default()),
+      hmac: Some(hmac::<fsb::Fsb224>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "fsb")]
     GeneratedHasherImplMeta {
@@ -98,6 +328,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Fsb256",
       hasher_command: "fsb256",
       hasher: Box::new(fsb::Fsb256::default()),
+      hmac: Some(hmac::<fsb::Fsb256>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "fsb")]
     GeneratedHasherImplMeta {
@@ -105,6 +341,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Fsb384",
       hasher_command: "fsb384",
       hasher: Box::new(fsb::Fsb384::default()),
+      hmac: Some(hmac::<fsb::Fsb384>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "fsb")]
     GeneratedHasherImplMeta {
@@ -112,6 +354,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Fsb512",
       hasher_command: "fsb512",
       hasher: Box::new(fsb::Fsb512::default()),
+      hmac: Some(hmac::<fsb::Fsb512>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "gost94")]
     GeneratedHasherImplMeta {
@@ -119,6 +367,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Gost94CryptoPro",
       hasher_command: "gost94-crypto-pro",
       hasher: Box::new(gost94::Gost94CryptoPro::default()),
+      hmac: Some(hmac::<gost94::Gost94CryptoPro>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Legacy,
+      multicodec: None,
     },
     #[cfg(feature = "gost94")]
     GeneratedHasherImplMeta {
@@ -126,6 +380,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Gost94UA",
       hasher_command: "gost94-ua",
       hasher: Box::new(gost94::Gost94UA::default()),
+      hmac: Some(hmac::<gost94::Gost94UA>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Legacy,
+      multicodec: None,
     },
     #[cfg(feature = "gost94")]
     GeneratedHasherImplMeta {
@@ -133,6 +393,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Gost94s2015",
       hasher_command: "gost94-2015",
       hasher: Box::new(gost94::Gost94s2015::default()),
+      hmac: Some(hmac::<gost94::Gost94s2015>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Legacy,
+      multicodec: None,
     },
     #[cfg(feature = "groestl")]
     GeneratedHasherImplMeta {
@@ -140,6 +406,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Groestl224",
       hasher_command: "groestl224",
       hasher: Box::new(groestl::Groestl224::default()),
+      hmac: Some(hmac::<groestl::Groestl224>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "groestl")]
     GeneratedHasherImplMeta {
@@ -147,6 +419,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Groestl256",
       hasher_command: "groestl256",
       hasher: Box::new(groestl::Groestl256::default()),
+      hmac: Some(hmac::<groestl::Groestl256>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "groestl")]
     GeneratedHasherImplMeta {
@@ -154,6 +432,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Groestl384",
       hasher_command: "groestl384",
       hasher: Box::new(groestl::Groestl384::default()),
+      hmac: Some(hmac::<groestl::Groestl384>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "groestl")]
     GeneratedHasherImplMeta {
@@ -161,6 +445,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Groestl512",
       hasher_command: "groestl512",
       hasher: Box::new(groestl::Groestl512::default()),
+      hmac: Some(hmac::<groestl::Groestl512>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "jh")]
     GeneratedHasherImplMeta {
@@ -168,6 +458,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Jh224",
       hasher_command: "jh224",
       hasher: Box::new(jh::Jh224::default()),
+      hmac: Some(hmac::<jh::Jh224>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "jh")]
     GeneratedHasherImplMeta {
@@ -175,6 +471,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Jh256",
       hasher_command: "jh256",
       hasher: Box::new(jh::Jh256::default()),
+      hmac: Some(hmac::<jh::Jh256>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "jh")]
     GeneratedHasherImplMeta {
@@ -182,6 +484,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Jh384",
       hasher_command: "jh384",
       hasher: Box::new(jh::Jh384::default()),
+      hmac: Some(hmac::<jh::Jh384>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "jh")]
     GeneratedHasherImplMeta {
@@ -189,6 +497,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Jh512",
       hasher_command: "jh512",
       hasher: Box::new(jh::Jh512::default()),
+      hmac: Some(hmac::<jh::Jh512>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "md2")]
     GeneratedHasherImplMeta {
@@ -196,6 +510,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Md2",
       hasher_command: "md2",
       hasher: Box::new(md2::Md2::default()),
+      hmac: Some(hmac::<md2::Md2>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Broken,
+      multicodec: None,
     },
     #[cfg(feature = "md4")]
     GeneratedHasherImplMeta {
@@ -203,6 +523,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Md4",
       hasher_command: "md4",
       hasher: Box::new(md4::Md4::default()),
+      hmac: Some(hmac::<md4::Md4>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Broken,
+      multicodec: Some(0xd4),
     },
     #[cfg(feature = "ripemd")]
     GeneratedHasherImplMeta {
@@ -210,6 +536,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Ripemd128",
       hasher_command: "ripemd128",
       hasher: Box::new(ripemd::Ripemd128::default()),
+      hmac: Some(hmac::<ripemd::Ripemd128>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "RFC 2286, test case 1",
+          key: "0x[0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b]",
+          data: CASE_1_DATA,
+          mac: "fbf61f9492aa4bbf81c172e84e0734db",
+        },
+        HmacVector {
+          source: "RFC 2286, test case 2",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac: "875f828862b6b334b427c55f9f7ff09b",
+        },
+      ],
+      xof: false,
+      security: Security::Legacy,
+      multicodec: Some(0x1052),
     },
     #[cfg(feature = "ripemd")]
     GeneratedHasherImplMeta {
@@ -217,6 +562,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Ripemd160",
       hasher_command: "ripemd160",
       hasher: Box::new(ripemd::Ripemd160::default()),
+      hmac: Some(hmac::<ripemd::Ripemd160>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "RFC 2286, test case 1",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac: "24cb4bd67d20fc1a5d2ed7732dcc39377f0a5668",
+        },
+        HmacVector {
+          source: "RFC 2286, test case 2",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac: "dda6c0213a485a9e24f4742064a7f033b43c4069",
+        },
+      ],
+      xof: false,
+      security: Security::Legacy,
+      multicodec: Some(0x1053),
     },
     #[cfg(feature = "ripemd")]
     GeneratedHasherImplMeta {
@@ -224,6 +588,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Ripemd256",
       hasher_command: "ripemd256",
       hasher: Box::new(ripemd::Ripemd256::default()),
+      hmac: Some(hmac::<ripemd::Ripemd256>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Legacy,
+      multicodec: Some(0x1054),
     },
     #[cfg(feature = "ripemd")]
     GeneratedHasherImplMeta {
@@ -231,6 +601,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Ripemd320",
       hasher_command: "ripemd320",
       hasher: Box::new(ripemd::Ripemd320::default()),
+      hmac: Some(hmac::<ripemd::Ripemd320>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Legacy,
+      multicodec: Some(0x1055),
     },
     #[cfg(feature = "sha1")]
     GeneratedHasherImplMeta {
@@ -238,6 +614,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Sha1",
       hasher_command: "sha1",
       hasher: Box::new(sha1::Sha1::default()),
+      hmac: Some(hmac::<sha1::Sha1>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "RFC 2202, test case 1",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac: "b617318655057264e28bc0b6fb378c8ef146be00",
+        },
+        HmacVector {
+          source: "RFC 2202, test case 2",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac: "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+        },
+      ],
+      xof: false,
+      security: Security::Broken,
+      multicodec: Some(0x11),
     },
     #[cfg(feature = "sha2")]
     GeneratedHasherImplMeta {
@@ -245,6 +640,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Sha224",
       hasher_command: "sha224",
       hasher: Box::new(sha2::Sha224::default()),
+      hmac: Some(hmac::<sha2::Sha224>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "RFC 4231, test case 1",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac: "896fb1128abbdf196832107cd49df33f47b4b1169912ba4f53684b22",
+        },
+        HmacVector {
+          source: "RFC 4231, test case 2",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac: "a30e01098bc6dbbf45690f3a7e9e6d0f8bbea2a39e6148008fd05e44",
+        },
+      ],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x1013),
     },
     #[cfg(feature = "sha2")]
     GeneratedHasherImplMeta {
@@ -252,6 +666,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Sha384",
       hasher_command: "sha384",
       hasher: Box::new(sha2::Sha384::default()),
+      hmac: Some(hmac::<sha2::Sha384>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "RFC 4231, test case 1",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac:
+            "afd03944d84895626b0825f4ab46907f15f9dadbe4101ec682aa034c7cebc59c\
+           faea9ea9076ede7f4af152e8b2fa9cb6",
+        },
+        HmacVector {
+          source: "RFC 4231, test case 2",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac:
+            "af45d2e376484031617f78d2b58a6b1b9c7ef464f5a01b47e42ec3736322445e\
+           8e2240ca5e69e2c78b3239ecfab21649",
+        },
+      ],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x20),
     },
     #[cfg(feature = "sha2")]
     GeneratedHasherImplMeta {
@@ -259,6 +696,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Sha512",
       hasher_command: "sha512",
       hasher: Box::new(sha2::Sha512::default()),
+      hmac: Some(hmac::<sha2::Sha512>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "RFC 4231, test case 1",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac:
+            "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cde\
+           daa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854",
+        },
+        HmacVector {
+          source: "RFC 4231, test case 2",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac:
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+           9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+        },
+      ],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x13),
     },
     #[cfg(feature = "sha2")]
     GeneratedHasherImplMeta {
@@ -266,6 +726,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Sha512_224",
       hasher_command: "sha512-224",
       hasher: Box::new(sha2::Sha512_224::default()),
+      hmac: Some(hmac::<sha2::Sha512_224>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac: "b244ba01307c0e7a8ccaad13b1067a4cf6b961fe0c6a20bda3d92039",
+        },
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac: "4a530b31a79ebcce36916546317c45f247d83241dfb818fd37254bde",
+        },
+      ],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x1014),
     },
     #[cfg(feature = "sha2")]
     GeneratedHasherImplMeta {
@@ -273,6 +752,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Sha512_256",
       hasher_command: "sha512-256",
       hasher: Box::new(sha2::Sha512_256::default()),
+      hmac: Some(hmac::<sha2::Sha512_256>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac:
+            "9f9126c3d9c3c330d760425ca8a217e31feae31bfe70196ff81642b868402eab",
+        },
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac:
+            "6df7b24630d5ccb2ee335407081a87188c221489768fa2020513b2d593359456",
+        },
+      ],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x1015),
     },
     #[cfg(feature = "sha3")]
     GeneratedHasherImplMeta {
@@ -280,6 +780,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Sha3_224",
       hasher_command: "sha3-224",
       hasher: Box::new(sha3::Sha3_224::default()),
+      hmac: Some(hmac::<sha3::Sha3_224>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac: "3b16546bbc7be2706a031dcafd56373d9884367641d8c59af3c860f7",
+        },
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac: "7fdb8dd88bd2f60d1b798634ad386811c2cfc85bfaf5d52bbace5e66",
+        },
+      ],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x17),
     },
     #[cfg(feature = "sha3")]
     GeneratedHasherImplMeta {
@@ -287,6 +806,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Sha3_256",
       hasher_command: "sha3-256",
       hasher: Box::new(sha3::Sha3_256::default()),
+      hmac: Some(hmac::<sha3::Sha3_256>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac:
+            "ba85192310dffa96e2a3a40e69774351140bb7185e1202cdcc917589f95e16bb",
+        },
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac:
+            "c7d4072e788877ae3596bbb0da73b887c9171f93095b294ae857fbe2645e1ba5",
+        },
+      ],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x16),
     },
     #[cfg(feature = "sha3")]
     GeneratedHasherImplMeta {
@@ -294,6 +834,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Sha3_384",
       hasher_command: "sha3-384",
       hasher: Box::new(sha3::Sha3_384::default()),
+      hmac: Some(hmac::<sha3::Sha3_384>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac:
+            "68d2dcf7fd4ddd0a2240c8a437305f61fb7334cfb5d0226e1bc27dc10a2e723a\
+           20d370b47743130e26ac7e3d532886bd",
+        },
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac:
+            "f1101f8cbf9766fd6764d2ed61903f21ca9b18f57cf3e1a23ca13508a93243ce\
+           48c045dc007f26a21b3f5e0e9df4c20a",
+        },
+      ],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x15),
     },
     #[cfg(feature = "sha3")]
     GeneratedHasherImplMeta {
@@ -301,6 +864,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Sha3_512",
       hasher_command: "sha3-512",
       hasher: Box::new(sha3::Sha3_512::default()),
+      hmac: Some(hmac::<sha3::Sha3_512>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac:
+            "eb3fbd4b2eaab8f5c504bd3a41465aacec15770a7cabac531e482f860b5ec7ba\
+           47ccb2c6f2afce8f88d22b6dc61380f23a668fd3888bb80537c0a0b86407689e",
+        },
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac:
+            "5a4bfeab6166427c7a3647b747292b8384537cdb89afb3bf5665e4c5e709350b\
+           287baec921fd7ca0ee7a0c31d022a95e1fc92ba9d77df883960275beb4e62024",
+        },
+      ],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x14),
     },
     #[cfg(feature = "sha3")]
     GeneratedHasherImplMeta {
@@ -308,6 +894,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Keccak224",
       hasher_command: "keccak224",
       hasher: Box::new(sha3::Keccak224::default()),
+      hmac: Some(hmac::<sha3::Keccak224>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x1a),
     },
     #[cfg(feature = "sha3")]
     GeneratedHasherImplMeta {
@@ -315,6 +907,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Keccak256",
       hasher_command: "keccak256",
       hasher: Box::new(sha3::Keccak256::default()),
+      hmac: Some(hmac::<sha3::Keccak256>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x1b),
     },
     #[cfg(feature = "sha3")]
     GeneratedHasherImplMeta {
@@ -322,6 +920,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Keccak384",
       hasher_command: "keccak384",
       hasher: Box::new(sha3::Keccak384::default()),
+      hmac: Some(hmac::<sha3::Keccak384>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x1c),
     },
     #[cfg(feature = "sha3")]
     GeneratedHasherImplMeta {
@@ -329,6 +933,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Keccak512",
       hasher_command: "keccak512",
       hasher: Box::new(sha3::Keccak512::default()),
+      hmac: Some(hmac::<sha3::Keccak512>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x1d),
     },
     #[cfg(feature = "shabal")]
     GeneratedHasherImplMeta {
@@ -336,6 +946,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Shabal192",
       hasher_command: "shabal192",
       hasher: Box::new(shabal::Shabal192::default()),
+      hmac: Some(hmac::<shabal::Shabal192>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "shabal")]
     GeneratedHasherImplMeta {
@@ -343,6 +959,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Shabal224",
       hasher_command: "shabal224",
       hasher: Box::new(shabal::Shabal224::default()),
+      hmac: Some(hmac::<shabal::Shabal224>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "shabal")]
     GeneratedHasherImplMeta {
@@ -350,6 +972,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Shabal256",
       hasher_command: "shabal256",
       hasher: Box::new(shabal::Shabal256::default()),
+      hmac: Some(hmac::<shabal::Shabal256>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "shabal")]
     GeneratedHasherImplMeta {
@@ -357,6 +985,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Shabal384",
       hasher_command: "shabal384",
       hasher: Box::new(shabal::Shabal384::default()),
+      hmac: Some(hmac::<shabal::Shabal384>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "shabal")]
     GeneratedHasherImplMeta {
@@ -364,6 +998,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Shabal512",
       hasher_command: "shabal512",
       hasher: Box::new(shabal::Shabal512::default()),
+      hmac: Some(hmac::<shabal::Shabal512>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "skein")]
     GeneratedHasherImplMeta {
@@ -371,6 +1011,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Skein256::<skein::consts::U32>",
       hasher_command: "skein256-32",
       hasher: Box::new(skein::Skein256::<skein::consts::U32>::default()),
+      hmac: Some(hmac::<skein::Skein256<skein::consts::U32>>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0xb320),
     },
     #[cfg(feature = "skein")]
     GeneratedHasherImplMeta {
@@ -378,6 +1024,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Skein256::<skein::consts::U64>",
       hasher_command: "skein256-64",
       hasher: Box::new(skein::Skein256::<skein::consts::U64>::default()),
+      hmac: Some(hmac::<skein::Skein256<skein::consts::U64>>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "skein")]
     GeneratedHasherImplMeta {
@@ -385,6 +1037,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Skein256::<skein::consts::U128>",
       hasher_command: "skein256-128",
       hasher: Box::new(skein::Skein256::<skein::consts::U128>::default()),
+      hmac: Some(hmac::<skein::Skein256<skein::consts::U128>>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "skein")]
     GeneratedHasherImplMeta {
@@ -392,6 +1050,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Skein512::<skein::consts::U32>",
       hasher_command: "skein512-32",
       hasher: Box::new(skein::Skein512::<skein::consts::U32>::default()),
+      hmac: Some(hmac::<skein::Skein512<skein::consts::U32>>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0xb340),
     },
     #[cfg(feature = "skein")]
     GeneratedHasherImplMeta {
@@ -399,6 +1063,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Skein512::<skein::consts::U64>",
       hasher_command: "skein512-64",
       hasher: Box::new(skein::Skein512::<skein::consts::U64>::default()),
+      hmac: Some(hmac::<skein::Skein512<skein::consts::U64>>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0xb360),
     },
     #[cfg(feature = "skein")]
     GeneratedHasherImplMeta {
@@ -406,6 +1076,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Skein512::<skein::consts::U128>",
       hasher_command: "skein512-128",
       hasher: Box::new(skein::Skein512::<skein::consts::U128>::default()),
+      hmac: Some(hmac::<skein::Skein512<skein::consts::U128>>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "skein")]
     GeneratedHasherImplMeta {
@@ -413,6 +1089,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Skein1024::<skein::consts::U32>",
       hasher_command: "skein1024-32",
       hasher: Box::new(skein::Skein1024::<skein::consts::U32>::default()),
+      hmac: Some(hmac::<skein::Skein1024<skein::consts::U32>>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0xb380),
     },
     #[cfg(feature = "skein")]
     GeneratedHasherImplMeta {
@@ -420,6 +1102,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Skein1024::<skein::consts::U64>",
       hasher_command: "skein1024-64",
       hasher: Box::new(skein::Skein1024::<skein::consts::U64>::default()),
+      hmac: Some(hmac::<skein::Skein1024<skein::consts::U64>>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0xb3a0),
     },
     #[cfg(feature = "skein")]
     GeneratedHasherImplMeta {
@@ -427,6 +1115,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Skein1024::<skein::consts::U128>",
       hasher_command: "skein1024-128",
       hasher: Box::new(skein::Skein1024::<skein::consts::U128>::default()),
+      hmac: Some(hmac::<skein::Skein1024<skein::consts::U128>>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0xb3e0),
     },
     #[cfg(feature = "sm3")]
     GeneratedHasherImplMeta {
@@ -434,6 +1128,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Sm3",
       hasher_command: "sm3",
       hasher: Box::new(sm3::Sm3::default()),
+      hmac: Some(hmac::<sm3::Sm3>),
+      keyed: None,
+      hmac_vectors: &[
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_1_KEY,
+          data: CASE_1_DATA,
+          mac:
+            "51b00d1fb49832bfb01c3ce27848e59f871d9ba938dc563b338ca964755cce70",
+        },
+        HmacVector {
+          source: "checked against OpenSSL",
+          key: CASE_2_KEY,
+          data: CASE_2_DATA,
+          mac:
+            "2e87f1d16862e6d964b50a5200bf2b10b764faa9680a296a2405f24bec39f882",
+        },
+      ],
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0x534d),
     },
     #[cfg(feature = "streebog")]
     GeneratedHasherImplMeta {
@@ -441,6 +1156,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Streebog256",
       hasher_command: "streebog256",
       hasher: Box::new(streebog::Streebog256::default()),
+      hmac: Some(hmac::<streebog::Streebog256>),
+      keyed: None,
+      hmac_vectors: &[HmacVector {
+        source: "RFC 7836",
+        key: RFC_7836_KEY,
+        data: RFC_7836_DATA,
+        mac: "a1aa5f7de402d7b3d323f2991c8d4534013137010a83754fd0af6d7cd4922ed9",
+      }],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "streebog")]
     GeneratedHasherImplMeta {
@@ -448,6 +1174,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Streebog512",
       hasher_command: "streebog512",
       hasher: Box::new(streebog::Streebog512::default()),
+      hmac: Some(hmac::<streebog::Streebog512>),
+      keyed: None,
+      hmac_vectors: &[HmacVector {
+        source: "RFC 7836",
+        key: RFC_7836_KEY,
+        data: RFC_7836_DATA,
+        mac: "a59bab22ecae19c65fbde6e5f4e9f5d8549d31f037f9df9b905500e171923a77\
+           3d5f1530f2ed7e964cb2eedc29e9ad2f3afe93b2814f79f5000ffc0366c251e6",
+      }],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
     },
     #[cfg(feature = "tiger")]
     GeneratedHasherImplMeta {
@@ -455,6 +1193,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Tiger",
       hasher_command: "tiger",
       hasher: Box::new(tiger::Tiger::default()),
+      hmac: Some(hmac::<tiger::Tiger>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Legacy,
+      multicodec: None,
     },
     #[cfg(feature = "tiger")]
     GeneratedHasherImplMeta {
@@ -462,6 +1206,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Tiger2",
       hasher_command: "tiger2",
       hasher: Box::new(tiger::Tiger2::default()),
+      hmac: Some(hmac::<tiger::Tiger2>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Legacy,
+      multicodec: None,
     },
     #[cfg(feature = "whirlpool")]
     GeneratedHasherImplMeta {
@@ -469,6 +1219,156 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       hasher_type_name: "Whirlpool",
       hasher_command: "whirlpool",
       hasher: Box::new(whirlpool::Whirlpool::default()),
+      hmac: Some(hmac::<whirlpool::Whirlpool>),
+      keyed: None,
+      hmac_vectors: &[],
+      xof: false,
+      security: Security::Ok,
+      multicodec: None,
+    },
+  ];
+
+  let command_algorithms: Vec<CommandAlgorithmMeta> = vec![
+    #[cfg(feature = "sha3")]
+    CommandAlgorithmMeta {
+      crate_name: "sha3",
+      type_name: "Shake128",
+      command: "shake128",
+      output_size: 32,
+      block_size: sha3::Shake128::block_size(),
+      keyed: false,
+      xof: true,
+      security: Security::Ok,
+      multicodec: Some(0x18),
+    },
+    #[cfg(feature = "sha3")]
+    CommandAlgorithmMeta {
+      crate_name: "sha3",
+      type_name: "Shake256",
+      command: "shake256",
+      output_size: 64,
+      block_size: sha3::Shake256::block_size(),
+      keyed: false,
+      xof: true,
+      security: Security::Ok,
+      multicodec: Some(0x19),
+    },
+    #[cfg(feature = "sha3")]
+    CommandAlgorithmMeta {
+      crate_name: "sha3",
+      type_name: "CShake128",
+      command: "cshake128",
+      output_size: 32,
+      block_size: sha3::CShake128::block_size(),
+      keyed: false,
+      xof: true,
+      security: Security::Ok,
+      multicodec: None,
+    },
+    #[cfg(feature = "sha3")]
+    CommandAlgorithmMeta {
+      crate_name: "sha3",
+      type_name: "CShake256",
+      command: "cshake256",
+      output_size: 64,
+      block_size: sha3::CShake256::block_size(),
+      keyed: false,
+      xof: true,
+      security: Security::Ok,
+      multicodec: None,
+    },
+    #[cfg(feature = "blake2")]
+    CommandAlgorithmMeta {
+      crate_name: "blake2",
+      type_name: "Blake2bVarCore",
+      command: "blake2b",
+      output_size: 64,
+      block_size: blake2::Blake2bVarCore::block_size(),
+      keyed: true,
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0xb240),
+    },
+    #[cfg(feature = "blake2")]
+    CommandAlgorithmMeta {
+      crate_name: "blake2",
+      type_name: "Blake2sVarCore",
+      command: "blake2s",
+      output_size: 32,
+      block_size: blake2::Blake2sVarCore::block_size(),
+      keyed: true,
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0xb260),
+    },
+    // Skein-512-512, built on Threefish in skein_var.rs
+    #[cfg(feature = "skein")]
+    CommandAlgorithmMeta {
+      crate_name: "skein",
+      type_name: "Skein",
+      command: "skein",
+      output_size: 64,
+      block_size: skein::Skein512::<skein::consts::U64>::block_size(),
+      keyed: true,
+      xof: false,
+      security: Security::Ok,
+      multicodec: Some(0xb360),
+    },
+  ];
+
+  // Known-answer vectors of `hash pbkdf2`, checked as its examples
+  let pbkdf2_vectors: Vec<Pbkdf2Vector> = vec![
+    #[cfg(feature = "sha1")]
+    Pbkdf2Vector {
+      source: "RFC 6070, test case 3",
+      algorithm: "sha1",
+      password: "'password'",
+      salt: "'salt'",
+      iterations: 4096,
+      length: None,
+      key: "4b007901b765489abead49d926f721d065a429c1",
+    },
+    #[cfg(feature = "sha1")]
+    Pbkdf2Vector {
+      source: "RFC 6070, test case 5",
+      algorithm: "sha1",
+      password: "'passwordPASSWORDpassword'",
+      salt: "'saltSALTsaltSALTsaltSALTsaltSALTsalt'",
+      iterations: 4096,
+      length: Some(25),
+      key: "3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038",
+    },
+    #[cfg(feature = "sha1")]
+    Pbkdf2Vector {
+      source: "RFC 6070, test case 6",
+      algorithm: "sha1",
+      password: "0x[7061737300776f7264]",
+      salt: "0x[7361006c74]",
+      iterations: 4096,
+      length: Some(16),
+      key: "56fa6aa75548099dcc37d7f03425e0c3",
+    },
+    #[cfg(feature = "sha2")]
+    Pbkdf2Vector {
+      source: "RFC 7914, section 11",
+      algorithm: "sha256",
+      password: "'passwd'",
+      salt: "'salt'",
+      iterations: 1,
+      length: Some(64),
+      key: "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+            49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783",
+    },
+    #[cfg(feature = "streebog")]
+    Pbkdf2Vector {
+      source: "R 50.1.111-2016",
+      algorithm: "streebog512",
+      password: "'password'",
+      salt: "'salt'",
+      iterations: 2,
+      length: None,
+      key: "5a585bafdfbb6e8830d6d68aa3b43ac00d2e4aebce01c9b31c2caed56f0236d4\
+            d34b2b8fbd2c4e89d54d46f50e47d45bbac301571743119e8d3c42ba66d348de",
     },
   ];
 
@@ -476,17 +1376,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
   let hashers_generated_path = Path::new(&out_dir).join("hashers_generated.rs");
   let commands_generated_path =
     Path::new(&out_dir).join("commands_generated.rs");
+  let registry_generated_path =
+    Path::new(&out_dir).join("registry_generated.rs");
+  let pbkdf2_generated_path = Path::new(&out_dir).join("pbkdf2_generated.rs");
   let mut hashers_generated_file =
     std::fs::File::create(hashers_generated_path).unwrap();
   let mut commands_generated_file =
     std::fs::File::create(commands_generated_path).unwrap();
+  let mut registry_generated_file =
+    std::fs::File::create(registry_generated_path).unwrap();
+  let mut pbkdf2_generated_file =
+    std::fs::File::create(pbkdf2_generated_path).unwrap();
 
   write!(
     hashers_generated_file,
     "use nu_protocol::{{Example, Span, Value, ShellError}};
 use nu_plugin::PluginCommand;
 use crate::HashesPlugin;
-use crate::hasher::{{Hasher, GenericHasher}};
+use crate::hasher::{{DynHasher, Hasher, GenericHasher}};
 "
   )?;
 
@@ -500,6 +1407,19 @@ pub fn commands() -> Vec<Box<dyn PluginCommand<Plugin = HashesPlugin>>> {{
 "
   )?;
 
+  write!(
+    registry_generated_file,
+    "use digest::{{core_api::BlockSizeUser, typenum::Unsigned}};
+use crate::{{
+  algorithms::Row,
+  hasher::Hasher,
+  registry::{{new_hasher, Algorithm, Security}},
+}};
+
+pub static ALGORITHMS: &[Algorithm] = &[
+"
+  )?;
+
   for mut hasher_impl_meta in hasher_impls {
     let feature_name =
       hasher_impl_meta.crate_name.replace("-", "_").to_uppercase();
@@ -508,7 +1428,12 @@ pub fn commands() -> Vec<Box<dyn PluginCommand<Plugin = HashesPlugin>>> {{
         .write_all(build_impl_str(&mut hasher_impl_meta).as_bytes())?;
       hashers_generated_file
         .write_all(build_test_str(&hasher_impl_meta).as_bytes())?;
+      registry_generated_file
+        .write_all(build_registry_entry_str(&hasher_impl_meta).as_bytes())?;
 
+      if hasher_impl_meta.xof {
+        continue;
+      }
       let crate_name = hasher_impl_meta.crate_name;
       let hasher_type_name = hasher_impl_meta.hasher_type_name;
       writeln!(
@@ -524,8 +1449,42 @@ pub fn commands() -> Vec<Box<dyn PluginCommand<Plugin = HashesPlugin>>> {{
 }}"
   )?;
 
+  write!(
+    registry_generated_file,
+    "];
+
+pub static COMMAND_ALGORITHMS: &[Row] = &[
+"
+  )?;
+  for meta in &command_algorithms {
+    registry_generated_file
+      .write_all(build_command_algorithm_str(meta).as_bytes())?;
+  }
+  write!(registry_generated_file, "];")?;
+
+  write!(
+    pbkdf2_generated_file,
+    "use nu_protocol::{{Example, Span, Value}};
+
+pub fn examples() -> Vec<Example<'static>> {{
+  vec![
+"
+  )?;
+  for vector in &pbkdf2_vectors {
+    pbkdf2_generated_file
+      .write_all(build_pbkdf2_example_str(vector).as_bytes())?;
+  }
+  write!(
+    pbkdf2_generated_file,
+    "
+  ]
+}}"
+  )?;
+
   hashers_generated_file.flush()?;
   commands_generated_file.flush()?;
+  registry_generated_file.flush()?;
+  pbkdf2_generated_file.flush()?;
 
   Ok(())
 }
@@ -559,6 +1518,71 @@ fn build_impl_str(meta: &mut GeneratedHasherImplMeta) -> String {
   let mut hasher = meta.hasher.clone();
   hasher.update(TEST_TEXT.as_bytes());
   let hash = hasher.clone().finalize();
+  let (hmac_impl, hmac_examples) = match meta.hmac {
+    Some(_) if !meta.hmac_vectors.is_empty() => (
+      "key: &[u8]) -> Option<Box<dyn DynHasher>> {
+    Some(crate::hasher::new_hmac::<Self>(key))",
+      meta
+        .hmac_vectors
+        .iter()
+        .map(|vector| build_hmac_example_str(command, vector))
+        .collect(),
+    ),
+    Some(hmac) => (
+      "key: &[u8]) -> Option<Box<dyn DynHasher>> {
+    Some(crate::hasher::new_hmac::<Self>(key))",
+      format!(
+        "
+      Example {{
+        description: \"Return the HMAC-{command} of a string, keyed with a string (regression output, no published test vector)\",
+        example: \"'{HMAC_TEST_KEY_TEXT}' | hash {command} --key '{HMAC_TEST_KEY}'\",
+        result: Some(Value::string(
+          \"{}\".to_owned(),
+          Span::test_data(),
+        )),
+      }},
+      Example {{
+        description: \"Return the HMAC-{command} of a string, keyed with binary (regression output, no published test vector)\",
+        example: \"'{HMAC_TEST_BINARY_KEY_TEXT}' | hash {command} --key 0x[{}]\",
+        result: Some(Value::string(
+          \"{}\".to_owned(),
+          Span::test_data(),
+        )),
+      }},",
+        to_hex(&hmac(
+          HMAC_TEST_KEY.as_bytes(),
+          HMAC_TEST_KEY_TEXT.as_bytes()
+        )),
+        to_hex(&HMAC_TEST_BINARY_KEY),
+        to_hex(&hmac(
+          &HMAC_TEST_BINARY_KEY,
+          HMAC_TEST_BINARY_KEY_TEXT.as_bytes()
+        )),
+      ),
+    ),
+    None => (
+      "_key: &[u8]) -> Option<Box<dyn DynHasher>> {
+    None",
+      String::new(),
+    ),
+  };
+  let encoding_examples: String = ENCODINGS
+    .iter()
+    .map(|(encoding, description, encode)| {
+      format!(
+        "
+      Example {{
+        description: \"Return the {command} hash of a string, {description}\",
+        example: \"'{TEST_TEXT}' | hash {command} --encoding {encoding}\",
+        result: Some(Value::string(
+          \"{}\".to_owned(),
+          Span::test_data(),
+        )),
+      }},",
+        encode(&hash)
+      )
+    })
+    .collect();
   format!(
     "
 impl Hasher for {crate_name}::{hasher_type_name} {{
@@ -566,6 +1590,9 @@ impl Hasher for {crate_name}::{hasher_type_name} {{
     \"{command}\"
   }}
 
+  fn hmac({hmac_impl}
+  }}
+
   fn examples() -> Vec<Example<'static>> {{
     vec![
       Example {{
@@ -583,21 +1610,17 @@ impl Hasher for {crate_name}::{hasher_type_name} {{
           vec![{}],
           Span::test_data(),
         )),
-      }},
+      }},{encoding_examples}
       Example {{
         description: \"Return the {command} hash of a file's contents\",
         example: \"open ./nu_0_24_1_windows.zip | hash {command}\",
         result: None,
-      }},
+      }},{hmac_examples}
     ]
   }}
 }}
 ",
-    hash
-      .iter()
-      .map(|b| format!("{b:02x?}"))
-      .collect::<Vec<_>>()
-      .join(""),
+    to_hex(&hash),
     hash
       .iter()
       .map(|b| format!("0x{b:02x?}"))
@@ -606,6 +1629,85 @@ impl Hasher for {crate_name}::{hasher_type_name} {{
   )
 }
 
+#[cfg(any(
+  feature = "ascon-hash",
+  feature = "belt-hash",
+  feature = "blake2",
+  feature = "blake3",
+  feature = "fsb",
+  feature = "gost94",
+  feature = "groestl",
+  feature = "jh",
+  feature = "md2",
+  feature = "md4",
+  feature = "ripemd",
+  feature = "sha1",
+  feature = "sha2",
+  feature = "sha3",
+  feature = "shabal",
+  feature = "skein",
+  feature = "sm3",
+  feature = "streebog",
+  feature = "tiger",
+  feature = "whirlpool",
+))]
+fn build_hmac_example_str(command: &str, vector: &HmacVector) -> String {
+  let HmacVector {
+    source,
+    key,
+    data,
+    mac,
+  } = vector;
+  let describe = |literal: &str| match literal.starts_with("0x[") {
+    true => "binary",
+    false => "a string",
+  };
+  let key_description = describe(key);
+  let data_description = describe(data);
+  format!(
+    "
+      Example {{
+        description: \"Return the HMAC-{command} of {data_description}, keyed with {key_description} ({source})\",
+        example: \"{data} | hash {command} --key {key}\",
+        result: Some(Value::string(
+          \"{mac}\".to_owned(),
+          Span::test_data(),
+        )),
+      }},"
+  )
+}
+
+fn build_pbkdf2_example_str(vector: &Pbkdf2Vector) -> String {
+  let Pbkdf2Vector {
+    source,
+    algorithm,
+    password,
+    salt,
+    iterations,
+    length,
+    key,
+  } = vector;
+  let length = match length {
+    Some(length) => format!(" --length {length}"),
+    None => String::new(),
+  };
+  format!(
+    "
+      Example {{
+        description: \"Derive a key with HMAC-{algorithm} ({source})\",
+        example: \"{password} | hash pbkdf2 --algorithm {algorithm} --iterations {iterations} --salt {salt}{length}\",
+        result: Some(Value::string(
+          \"{key}\".to_owned(),
+          Span::test_data(),
+        )),
+      }},"
+  )
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{b:02x?}")).collect()
+}
+
 #[cfg(any(
   feature = "ascon-hash",
   feature = "belt-hash",
@@ -648,6 +1750,122 @@ fn test_{test_name}_examples() -> Result<(), ShellError> {{
   )
 }
 
+#[cfg(any(
+  feature = "ascon-hash",
+  feature = "belt-hash",
+  feature = "blake2",
+  feature = "blake3",
+  feature = "fsb",
+  feature = "gost94",
+  feature = "groestl",
+  feature = "jh",
+  feature = "md2",
+  feature = "md4",
+  feature = "ripemd",
+  feature = "sha1",
+  feature = "sha2",
+  feature = "sha3",
+  feature = "shabal",
+  feature = "skein",
+  feature = "sm3",
+  feature = "streebog",
+  feature = "tiger",
+  feature = "whirlpool",
+))]
+fn build_registry_entry_str(meta: &GeneratedHasherImplMeta) -> String {
+  let crate_name = meta.crate_name;
+  let hasher_type_name = meta.hasher_type_name;
+  let command = meta.hasher_command;
+  let feature = crate_name.replace("_", "-");
+  let output_size = meta.hasher.output_size();
+  let keyed = match (meta.keyed, meta.hmac) {
+    (Some(keyed), _) => format!("Some({keyed})"),
+    (None, Some(_)) => format!(
+      "Some(crate::registry::new_hmac::<{crate_name}::{hasher_type_name}>)"
+    ),
+    (None, None) => "None".to_owned(),
+  };
+  let xof = meta.xof;
+  let security = &meta.security;
+  let multicodec = match meta.multicodec {
+    Some(code) => format!("Some(0x{code:x})"),
+    None => "None".to_owned(),
+  };
+  format!(
+    "  Algorithm {{
+    name: \"{command}\",
+    crate_name: \"{crate_name}\",
+    type_name: \"{hasher_type_name}\",
+    feature: \"{feature}\",
+    output_size: {output_size},
+    block_size: <<{crate_name}::{hasher_type_name} as BlockSizeUser>::BlockSize as Unsigned>::USIZE,
+    new: new_hasher::<{crate_name}::{hasher_type_name}>,
+    keyed: {keyed},
+    xof: {xof},
+    security: Security::{security:?},
+    multicodec: {multicodec},
+    hmac: <{crate_name}::{hasher_type_name} as Hasher>::hmac,
+  }},
+"
+  )
+}
+
+#[cfg(any(
+  feature = "ascon-hash",
+  feature = "belt-hash",
+  feature = "blake2",
+  feature = "blake3",
+  feature = "fsb",
+  feature = "gost94",
+  feature = "groestl",
+  feature = "jh",
+  feature = "md2",
+  feature = "md4",
+  feature = "ripemd",
+  feature = "sha1",
+  feature = "sha2",
+  feature = "sha3",
+  feature = "shabal",
+  feature = "skein",
+  feature = "sm3",
+  feature = "streebog",
+  feature = "tiger",
+  feature = "whirlpool",
+))]
+fn build_command_algorithm_str(meta: &CommandAlgorithmMeta) -> String {
+  let CommandAlgorithmMeta {
+    crate_name,
+    type_name,
+    command,
+    output_size,
+    block_size,
+    keyed,
+    xof,
+    security,
+    multicodec,
+  } = meta;
+  let feature = crate_name.replace("_", "-");
+  let multicodec = match multicodec {
+    Some(code) => format!("Some(0x{code:x})"),
+    None => "None".to_owned(),
+  };
+  format!(
+    "  Row {{
+    name: \"{command}\",
+    crate_name: \"{crate_name}\",
+    type_name: \"{type_name}\",
+    feature: \"{feature}\",
+    output_size: {output_size},
+    block_size: {block_size},
+    keyed: {keyed},
+    xof: {xof},
+    security: Security::{security:?},
+    multicodec: {multicodec},
+  }},
+"
+  )
+}
+
 #[cfg(not(any(
   feature = "ascon-hash",
   feature = "belt-hash",
@@ -0,0 +1,259 @@
+//! Contains a command that lists hash algorithms compiled into the plugin.
+
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  record,
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  Signature,
+  Span,
+  Type,
+  Value,
+};
+
+use crate::{
+  registry::{Security, ALGORITHMS, COMMAND_ALGORITHMS},
+  HashesPlugin,
+};
+
+pub struct Algorithms;
+
+impl PluginCommand for Algorithms {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash algorithms"
+  }
+
+  fn signature(&self) -> Signature {
+    Signature::build(self.name())
+      .category(Category::Hash)
+      .input_output_types(vec![(Type::Nothing, Type::table())])
+  }
+
+  fn description(&self) -> &str {
+    "List hash algorithms available in the plugin."
+  }
+
+  fn extra_description(&self) -> &str {
+    "Algorithms are enabled with cargo features of the same name. Output and \
+     block sizes are given in bits and bytes respectively. For \
+     extendable-output functions the default output size is shown."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "List all available algorithms",
+        example: "hash algorithms",
+        result: None,
+      },
+      Example {
+        description: "List algorithms, that are not broken and accept a key",
+        example: "hash algorithms | where keyed and security != broken",
+        result: None,
+      },
+      Example {
+        description: "Check which cargo features the plugin was built with",
+        example: "hash algorithms | get feature | uniq",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    _input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let mut rows: Vec<(&str, Value)> = ALGORITHMS
+      .iter()
+      .map(|algorithm| Row {
+        name: algorithm.name,
+        crate_name: algorithm.crate_name,
+        type_name: algorithm.type_name,
+        feature: algorithm.feature,
+        output_size: algorithm.output_size,
+        block_size: algorithm.block_size,
+        keyed: algorithm.keyed.is_some(),
+        xof: algorithm.xof,
+        security: algorithm.security,
+        multicodec: algorithm.multicodec,
+      })
+      .chain(COMMAND_ALGORITHMS.iter().copied())
+      .map(|row| (row.crate_name, row.into_value(head)))
+      .collect();
+    // Keep algorithms of the same crate together
+    rows.sort_by_key(|(crate_name, _)| *crate_name);
+
+    Ok(
+      Value::list(rows.into_iter().map(|(_, row)| row).collect(), head)
+        .into_pipeline_data(),
+    )
+  }
+}
+
+/// A row of the table of algorithms. Rows of commands, that aren't in the
+/// registry, are generated by the build script as well.
+#[derive(Clone, Copy)]
+pub struct Row {
+  pub name: &'static str,
+  pub crate_name: &'static str,
+  pub type_name: &'static str,
+  pub feature: &'static str,
+  /// Output size in bytes.
+  pub output_size: usize,
+  /// Block size in bytes.
+  pub block_size: usize,
+  pub keyed: bool,
+  pub xof: bool,
+  pub security: Security,
+  pub multicodec: Option<u64>,
+}
+
+impl Row {
+  fn into_value(self, span: Span) -> Value {
+    Value::record(
+      record! {
+        "name" => Value::string(self.name, span),
+        "crate" => Value::string(self.crate_name, span),
+        "type" => Value::string(self.type_name, span),
+        "feature" => Value::string(self.feature, span),
+        "output_bits" => Value::int(self.output_size as i64 * 8, span),
+        "block_size" => Value::int(self.block_size as i64, span),
+        "keyed" => Value::bool(self.keyed, span),
+        "xof" => Value::bool(self.xof, span),
+        "security" => Value::string(self.security.as_str(), span),
+        "multicodec" => match self.multicodec {
+          Some(code) => Value::int(code as i64, span),
+          None => Value::nothing(span),
+        },
+      },
+      span,
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nu_plugin::Plugin;
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::ShellError;
+
+  use super::*;
+  #[cfg(feature = "blake2")]
+  use crate::blake2_var;
+  #[cfg(feature = "skein")]
+  use crate::skein_var;
+  use crate::xof;
+
+  #[test]
+  fn test_lists_registered_commands() -> Result<(), ShellError> {
+    let names: Vec<String> =
+      PluginTest::new("hash algorithms", HashesPlugin.into())?
+        .eval("hash algorithms")?
+        .into_value(Span::test_data())?
+        .into_list()?
+        .into_iter()
+        .map(|row| {
+          row
+            .into_record()?
+            .get("name")
+            .unwrap()
+            .clone()
+            .into_string()
+        })
+        .collect::<Result<_, _>>()?;
+    let commands: Vec<String> = HashesPlugin
+      .commands()
+      .iter()
+      .map(|command| command.name().to_owned())
+      .collect();
+
+    for name in &names {
+      assert!(commands.contains(&format!("hash {name}")), "{name}");
+    }
+    let mut commands = crate::commands_generated::commands();
+    commands.extend(xof::commands());
+    #[cfg(feature = "blake2")]
+    commands.extend(blake2_var::commands());
+    #[cfg(feature = "skein")]
+    commands.push(Box::new(skein_var::SkeinHasher));
+    for command in commands {
+      let name = command.name().trim_start_matches("hash ");
+      assert!(names.iter().any(|n| n == name), "{name}");
+    }
+    Ok(())
+  }
+
+  #[cfg(feature = "blake2")]
+  #[test]
+  fn test_blake2_rows() -> Result<(), ShellError> {
+    let rows = PluginTest::new("hash algorithms", HashesPlugin.into())?
+      .eval("hash algorithms")?
+      .into_value(Span::test_data())?
+      .into_list()?;
+    let row = |name: &str| {
+      rows
+        .iter()
+        .find(|row| {
+          row.get_data_by_key("name") == Some(Value::test_string(name))
+        })
+        .unwrap()
+        .clone()
+    };
+    for (name, output_bits, block_size, multicodec) in
+      [("blake2b", 512, 128, 0xb240), ("blake2s", 256, 64, 0xb260)]
+    {
+      let row = row(name);
+      assert_eq!(row.get_data_by_key("keyed"), Some(Value::test_bool(true)));
+      assert_eq!(row.get_data_by_key("xof"), Some(Value::test_bool(false)));
+      assert_eq!(
+        row.get_data_by_key("output_bits"),
+        Some(Value::test_int(output_bits))
+      );
+      assert_eq!(
+        row.get_data_by_key("block_size"),
+        Some(Value::test_int(block_size))
+      );
+      assert_eq!(
+        row.get_data_by_key("multicodec"),
+        Some(Value::test_int(multicodec))
+      );
+    }
+    Ok(())
+  }
+
+  #[cfg(feature = "skein")]
+  #[test]
+  fn test_skein_row() -> Result<(), ShellError> {
+    let rows = PluginTest::new("hash algorithms", HashesPlugin.into())?
+      .eval("hash algorithms")?
+      .into_value(Span::test_data())?
+      .into_list()?;
+    let row = rows
+      .iter()
+      .find(|row| {
+        row.get_data_by_key("name") == Some(Value::test_string("skein"))
+      })
+      .unwrap();
+    assert_eq!(row.get_data_by_key("keyed"), Some(Value::test_bool(true)));
+    assert_eq!(
+      row.get_data_by_key("output_bits"),
+      Some(Value::test_int(512))
+    );
+    assert_eq!(row.get_data_by_key("block_size"), Some(Value::test_int(64)));
+    // skein512-512
+    assert_eq!(
+      row.get_data_by_key("multicodec"),
+      Some(Value::test_int(0xb360))
+    );
+    Ok(())
+  }
+}
@@ -0,0 +1,511 @@
+//! Contains BLAKE2 commands, that take all parameters of the algorithm:
+//! output length, key, salt and personalization. The hashers generated by the
+//! build script have fixed output sizes and take none of them.
+//! Input handling mirrors the one of `GenericHasher`.
+
+use std::{io::Write, marker::PhantomData, ops::Not};
+
+use digest::{
+  block_buffer::Lazy,
+  core_api::{Block, Buffer, BufferKindUser, UpdateCore, VariableOutputCore},
+  typenum::{IsLess, True, U256},
+};
+use nu_cmd_base::input_handler::CmdArgument;
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  ast::CellPath,
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+
+use crate::{
+  encoding::{self, Encoding},
+  hasher::hash_value,
+  multihash,
+  parallel,
+  HashesPlugin,
+};
+
+/// Core of a BLAKE2 variant with variable output length.
+pub trait Blake2Core:
+  VariableOutputCore
+  + UpdateCore
+  + BufferKindUser<BufferKind = Lazy, BlockSize: IsLess<U256, Output = True>>
+  + Clone
+  + Send
+  + Sync
+  + 'static
+{
+  /// Maximum output and key length in bytes.
+  const MAX_LENGTH: usize;
+  /// Maximum salt and personalization length in bytes.
+  const MAX_SALT_LENGTH: usize;
+  /// Multicodec code of one byte long digests. Codes of longer digests
+  /// follow it.
+  const MULTICODEC: u64;
+
+  fn name() -> &'static str;
+  fn examples() -> Vec<Example<'static>>;
+  fn new_with_params(
+    salt: &[u8],
+    persona: &[u8],
+    key_size: usize,
+    output_size: usize,
+  ) -> Self;
+}
+
+pub fn commands() -> Vec<Box<dyn PluginCommand<Plugin = HashesPlugin>>> {
+  vec![
+    Box::new(GenericBlake2::<blake2::Blake2bVarCore>::default()),
+    Box::new(GenericBlake2::<blake2::Blake2sVarCore>::default()),
+  ]
+}
+
+/// BLAKE2 hasher with parameters known at runtime.
+#[derive(Clone)]
+pub struct Blake2<C: Blake2Core> {
+  core: C,
+  buffer: Buffer<C>,
+  length: usize,
+}
+
+impl<C: Blake2Core> Blake2<C> {
+  /// Creates a hasher. Lengths of parameters must be checked beforehand.
+  fn new(length: usize, key: &[u8], salt: &[u8], persona: &[u8]) -> Self {
+    let core = C::new_with_params(salt, persona, key.len(), length);
+    // The key is padded to a full block, that is hashed before the data
+    let buffer = if key.is_empty() {
+      Buffer::<C>::default()
+    } else {
+      let mut block = Block::<C>::default();
+      block[..key.len()].copy_from_slice(key);
+      Buffer::<C>::new(&block)
+    };
+    Self {
+      core,
+      buffer,
+      length,
+    }
+  }
+
+  fn update(&mut self, data: &[u8]) {
+    let Self { core, buffer, .. } = self;
+    buffer.digest_blocks(data, |blocks| core.update_blocks(blocks));
+  }
+
+  fn finalize(mut self) -> Vec<u8> {
+    let mut output = Default::default();
+    self
+      .core
+      .finalize_variable_core(&mut self.buffer, &mut output);
+    output[..self.length].to_vec()
+  }
+}
+
+impl<C: Blake2Core> Write for Blake2<C> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+#[derive(Clone)]
+pub struct GenericBlake2<C: Blake2Core> {
+  name: String,
+  description: String,
+  _core: PhantomData<C>,
+}
+
+impl<C: Blake2Core> Default for GenericBlake2<C> {
+  fn default() -> Self {
+    Self {
+      name: format!("hash {}", C::name()),
+      description: format!(
+        "Hash a value using the {} hash algorithm with custom parameters.",
+        C::name()
+      ),
+      _core: PhantomData,
+    }
+  }
+}
+
+struct Arguments<C: Blake2Core> {
+  cell_paths: Option<Vec<CellPath>>,
+  encoding: Encoding,
+  multihash: Option<u64>,
+  hasher: Blake2<C>,
+}
+
+impl<C: Blake2Core> CmdArgument for Arguments<C> {
+  fn take_cell_paths(&mut self) -> Option<Vec<CellPath>> {
+    self.cell_paths.take()
+  }
+}
+
+impl<C: Blake2Core> PluginCommand for GenericBlake2<C> {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    &self.name
+  }
+
+  fn signature(&self) -> Signature {
+    let parameter = |signature: Signature, name: &str, description: &str| {
+      signature.named(
+        name,
+        SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+        format!("{description}, up to {} bytes", C::MAX_SALT_LENGTH),
+        None,
+      )
+    };
+    let signature =
+      parallel::signature(multihash::signature(encoding::signature(
+        Signature::build(self.name())
+          .category(Category::Hash)
+          .input_output_types(vec![
+            (Type::Binary, Type::Any),
+            (Type::String, Type::Any),
+            (Type::table(), Type::table()),
+            (Type::record(), Type::record()),
+          ])
+          .allow_variants_without_examples(true),
+      )))
+      .named(
+        "length",
+        SyntaxShape::Int,
+        format!("Output length in bytes, up to {}", C::MAX_LENGTH),
+        Some('l'),
+      )
+      .named(
+        "key",
+        SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+        format!("Key of a MAC, up to {} bytes", C::MAX_LENGTH),
+        Some('k'),
+      );
+    let signature = parameter(signature, "salt", "Salt");
+    parameter(signature, "persona", "Personalization string").rest(
+      "rest",
+      SyntaxShape::CellPath,
+      format!("Optionally {} hash data by cell path.", C::name()),
+    )
+  }
+
+  fn description(&self) -> &str {
+    &self.description
+  }
+
+  fn extra_description(&self) -> &str {
+    "Unlike HMAC, keyed BLAKE2 is a MAC on its own. Salt and personalization \
+     strings are padded with zeros."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    C::examples()
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let encoding = Encoding::from_call(call)?;
+    let length = match call.get_flag::<Spanned<i64>>("length")? {
+      Some(length) => usize::try_from(length.item)
+        .ok()
+        .filter(|length| (1..=C::MAX_LENGTH).contains(length))
+        .ok_or_else(|| {
+          LabeledError::new("Invalid output length").with_label(
+            format!("output length must be from 1 to {} bytes", C::MAX_LENGTH),
+            length.span,
+          )
+        })?,
+      None => C::MAX_LENGTH,
+    };
+    let key = parameter(call, "key", C::MAX_LENGTH)?;
+    let salt = parameter(call, "salt", C::MAX_SALT_LENGTH)?;
+    let persona = parameter(call, "persona", C::MAX_SALT_LENGTH)?;
+    let multihash = if salt.is_empty() && persona.is_empty() {
+      let code = C::MULTICODEC + length as u64 - 1;
+      multihash::from_call(call, C::name(), Some(code))?
+    } else {
+      let name = format!("{} with salt or personalization", C::name());
+      multihash::from_call(call, &name, None)?
+    };
+    let mut hasher = Blake2::<C>::new(length, &key, &salt, &persona);
+    let cell_paths: Vec<CellPath> = call.rest(0)?;
+    let cell_paths = cell_paths.is_empty().not().then_some(cell_paths);
+
+    if let PipelineData::ByteStream(stream, ..) = input {
+      stream.write_to(&mut hasher)?;
+      let digest = hasher.finalize();
+      let digest = match multihash {
+        Some(code) => multihash::encode(code, digest),
+        None => digest,
+      };
+      Ok(encoding.encode(digest, head).into_pipeline_data())
+    } else {
+      parallel::operate(
+        action::<C>,
+        Arguments {
+          cell_paths,
+          encoding,
+          multihash,
+          hasher,
+        },
+        input,
+        head,
+        engine.signals(),
+        parallel::from_call(engine, call)?,
+      )
+      .map_err(Into::into)
+    }
+  }
+}
+
+/// Reads a string or binary parameter of at most `max_length` bytes.
+fn parameter(
+  call: &EvaluatedCall,
+  name: &str,
+  max_length: usize,
+) -> Result<Vec<u8>, LabeledError> {
+  let Some(parameter) = call.get_flag::<Spanned<Vec<u8>>>(name)? else {
+    return Ok(Vec::new());
+  };
+  if parameter.item.len() > max_length {
+    return Err(
+      LabeledError::new(format!("Invalid {name} length")).with_label(
+        format!(
+          "{} bytes long, but can be up to {max_length} bytes long",
+          parameter.item.len()
+        ),
+        parameter.span,
+      ),
+    );
+  }
+  Ok(parameter.item)
+}
+
+fn action<C: Blake2Core>(
+  input: &Value,
+  args: &Arguments<C>,
+  _span: Span,
+) -> Value {
+  hash_value(input, args.encoding, |bytes| {
+    let mut hasher = args.hasher.clone();
+    hasher.update(bytes);
+    let digest = hasher.finalize();
+    match args.multihash {
+      Some(code) => multihash::encode(code, digest),
+      None => digest,
+    }
+  })
+}
+
+impl Blake2Core for blake2::Blake2bVarCore {
+  const MAX_LENGTH: usize = 64;
+  const MAX_SALT_LENGTH: usize = 16;
+  const MULTICODEC: u64 = 0xb201;
+
+  fn name() -> &'static str {
+    "blake2b"
+  }
+
+  fn examples() -> Vec<Example<'static>> {
+    vec![
+      Example {
+        description: "Return the blake2b hash of a string (RFC 7693, \
+                      appendix A)",
+        example: "'abc' | hash blake2b",
+        result: Some(Value::string(
+          "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+           7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the blake2b-256 hash of a string",
+        example: "'abc' | hash blake2b --length 32",
+        result: Some(Value::string(
+          "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the keyed blake2b hash of an empty string \
+                      (official test vector)",
+        example: "'' | hash blake2b --key 0x[000102030405060708090a0b0c0d0e0f\
+                  101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d\
+                  2e2f303132333435363738393a3b3c3d3e3f]",
+        result: Some(Value::string(
+          "10ebb67700b1868efb4417987acf4690ae9d972fb7a590c2f02871799aaa4786\
+           b5e996e8f0f4eb981fc214b005f42d2ff4233499391653df7aefcbc13fc51568",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the keyed, salted and personalized blake2b-256 \
+                      hash of a string",
+        example: "'abc' | hash blake2b --length 32 --key secret --salt \
+                  0123456789abcdef --persona my-protocol-v1",
+        result: Some(Value::string(
+          "93c64c4c9d22f4cd876b296874e89a3ad6db657947a98697e27b3f34f1e7e033",
+          Span::test_data(),
+        )),
+      },
+    ]
+  }
+
+  fn new_with_params(
+    salt: &[u8],
+    persona: &[u8],
+    key_size: usize,
+    output_size: usize,
+  ) -> Self {
+    Self::new_with_params(salt, persona, key_size, output_size)
+  }
+}
+
+impl Blake2Core for blake2::Blake2sVarCore {
+  const MAX_LENGTH: usize = 32;
+  const MAX_SALT_LENGTH: usize = 8;
+  const MULTICODEC: u64 = 0xb241;
+
+  fn name() -> &'static str {
+    "blake2s"
+  }
+
+  fn examples() -> Vec<Example<'static>> {
+    vec![
+      Example {
+        description: "Return the blake2s hash of a string (RFC 7693, \
+                      appendix B)",
+        example: "'abc' | hash blake2s",
+        result: Some(Value::string(
+          "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the blake2s-128 hash of a string",
+        example: "'abc' | hash blake2s --length 16",
+        result: Some(Value::string(
+          "aa4938119b1dc7b87cbad0ffd200d0ae",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the keyed blake2s hash of an empty string \
+                      (official test vector)",
+        example: "'' | hash blake2s --key 0x[000102030405060708090a0b0c0d0e0f\
+                  101112131415161718191a1b1c1d1e1f]",
+        result: Some(Value::string(
+          "48a8997da407876b3d79c0d92325ad3b89cbb754d86ab71aee047ad345fd2c49",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the salted and personalized blake2s-128 hash of \
+                      a string",
+        example: "'abc' | hash blake2s --length 16 --salt saltsalt --persona \
+                  proto-v1",
+        result: Some(Value::string(
+          "6c0e84a0a443fa2ed4d2352c51092cb5",
+          Span::test_data(),
+        )),
+      },
+    ]
+  }
+
+  fn new_with_params(
+    salt: &[u8],
+    persona: &[u8],
+    key_size: usize,
+    output_size: usize,
+  ) -> Self {
+    Self::new_with_params(salt, persona, key_size, output_size)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::ShellError;
+
+  use super::*;
+
+  fn test_examples<C: Blake2Core>() -> Result<(), ShellError> {
+    let command = GenericBlake2::<C>::default();
+    PluginTest::new(&command.name, HashesPlugin.into())?
+      .test_examples(&command.examples())
+  }
+
+  #[test]
+  fn test_blake2b_examples() -> Result<(), ShellError> {
+    test_examples::<blake2::Blake2bVarCore>()
+  }
+
+  #[test]
+  fn test_blake2s_examples() -> Result<(), ShellError> {
+    test_examples::<blake2::Blake2sVarCore>()
+  }
+
+  #[test]
+  fn test_parameters() -> Result<(), ShellError> {
+    let mut plugin_test = PluginTest::new("hash blake2s", HashesPlugin.into())?;
+    let mut hash = |args: &str| {
+      plugin_test
+        .eval(&format!("'abc' | hash blake2s {args}"))
+        .and_then(|data| data.into_value(Span::test_data()))
+    };
+    assert!(hash("--length 0").is_err());
+    assert!(hash("--length 33").is_err());
+    assert!(hash("--salt 123456789").is_err());
+    assert!(hash("--persona 123456789").is_err());
+    assert!(hash("--key 0123456789abcdef0123456789abcdef!").is_err());
+    assert!(hash("--salt 12345678 --format multihash").is_err());
+    // blake2s-128 has code 0xb250, its varint takes 3 bytes
+    assert_eq!(
+      hash("--length 16 --format multihash")?,
+      Value::test_string("d0e40210aa4938119b1dc7b87cbad0ffd200d0ae")
+    );
+    Ok(())
+  }
+
+  /// Rows of `hash algorithms` are generated from the list in the build
+  /// script, check them against the commands.
+  #[test]
+  fn test_algorithm_rows() {
+    fn check<C: Blake2Core>() {
+      let row = crate::registry::COMMAND_ALGORITHMS
+        .iter()
+        .find(|row| row.name == C::name())
+        .unwrap();
+      assert_eq!(row.output_size, C::MAX_LENGTH, "{}", C::name());
+      assert_eq!(row.block_size, C::block_size(), "{}", C::name());
+      assert_eq!(
+        row.multicodec,
+        Some(C::MULTICODEC + C::MAX_LENGTH as u64 - 1),
+        "{}",
+        C::name()
+      );
+    }
+    check::<blake2::Blake2bVarCore>();
+    check::<blake2::Blake2sVarCore>();
+  }
+}
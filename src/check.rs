@@ -0,0 +1,323 @@
+//! Contains a command that verifies files against a checksum file, like
+//! `sha512sum --check` does.
+
+use std::{io::ErrorKind, path::PathBuf};
+
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  record,
+  shell_error::io::{self, IoError},
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  ShellError,
+  Signature,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+
+use crate::{
+  checksums::{self, ChecksumLine},
+  file::digest_file,
+  hasher::to_hex,
+  registry,
+  HashesPlugin,
+};
+
+pub struct Check;
+
+impl PluginCommand for Check {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash check"
+  }
+
+  fn signature(&self) -> Signature {
+    Signature::build(self.name())
+      .category(Category::Hash)
+      .input_output_types(vec![
+        (Type::String, Type::record()),
+        (Type::Binary, Type::record()),
+      ])
+      .named(
+        "algorithm",
+        SyntaxShape::String,
+        "Hash algorithm of lines without a BSD tag",
+        Some('a'),
+      )
+      .switch(
+        "strict",
+        "Fail on improperly formatted checksum lines",
+        None,
+      )
+      .switch(
+        "ignore-missing",
+        "Don't fail or report status for missing files",
+        None,
+      )
+  }
+
+  fn description(&self) -> &str {
+    "Verify files against a checksum file in GNU or BSD tag format."
+  }
+
+  fn extra_description(&self) -> &str {
+    "Lines in GNU format, like `<hex>  <path>`, are hashed with the algorithm \
+     given by `--algorithm`. Lines in BSD tag format, like `SHA3-256 (path) = \
+     <hex>`, name their algorithm. Relative paths are resolved against the \
+     current directory.
+
+Returns a record with the status of each file, a summary and the `success` \
+     field, that is false whenever `sha512sum --check` would exit with an \
+     error."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Verify files listed in a GNU checksum file",
+        example: "open --raw SHA512SUMS | hash check --algorithm sha512",
+        result: None,
+      },
+      Example {
+        description: "Verify only downloaded files listed in a BSD tag file",
+        example: "open --raw CHECKSUMS | hash check --ignore-missing",
+        result: None,
+      },
+      Example {
+        description: "List files that failed verification",
+        example: "open --raw SHA3SUMS | hash check --algorithm sha3-256 | get \
+                  files | where status != ok",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let algorithm: Option<Spanned<String>> = call.get_flag("algorithm")?;
+    let algorithm = algorithm.as_ref().map(registry::find).transpose()?;
+    let strict = call.has_flag("strict")?;
+    let ignore_missing = call.has_flag("ignore-missing")?;
+    let text = input_text(input)?;
+    let signals = engine.signals();
+
+    let mut cwd: Option<PathBuf> = None;
+    let mut files = Vec::new();
+    let (mut ok, mut failed, mut missing, mut improperly_formatted) =
+      (0, 0, 0, 0);
+    let mut formatted = 0;
+    for line in text.lines() {
+      if line.starts_with('#') || line.trim().is_empty() {
+        continue;
+      }
+      let Some(ChecksumLine { tag, digest, path }) =
+        checksums::parse_line(line)
+      else {
+        improperly_formatted += 1;
+        continue;
+      };
+      let algorithm = match &tag {
+        Some(tag) => match checksums::algorithm_by_tag(tag) {
+          Some(algorithm) => algorithm,
+          None => {
+            improperly_formatted += 1;
+            continue;
+          }
+        },
+        None => algorithm.ok_or_else(|| {
+          LabeledError::new("Missing algorithm")
+            .with_label(
+              "checksum lines without BSD tags require an algorithm",
+              head,
+            )
+            .with_help("specify the algorithm with --algorithm")
+        })?,
+      };
+      if digest.len() != algorithm.output_size * 2 {
+        improperly_formatted += 1;
+        continue;
+      }
+      formatted += 1;
+
+      let full_path = if PathBuf::from(&path).is_absolute() {
+        PathBuf::from(&path)
+      } else {
+        if cwd.is_none() {
+          cwd = Some(PathBuf::from(engine.get_current_dir()?));
+        }
+        cwd.as_ref().expect("initialized above").join(&path)
+      };
+      let status = match digest_file(algorithm, None, &full_path, signals, head)
+      {
+        Ok((_, actual)) if to_hex(&actual) == digest => {
+          ok += 1;
+          "ok"
+        }
+        Ok(_) => {
+          failed += 1;
+          "FAILED"
+        }
+        Err(ShellError::Io(IoError {
+          kind:
+            io::ErrorKind::Std(ErrorKind::NotFound, ..)
+            | io::ErrorKind::FileNotFound,
+          ..
+        })) => {
+          missing += 1;
+          if ignore_missing {
+            continue;
+          }
+          "missing"
+        }
+        Err(ShellError::Io(_)) => {
+          failed += 1;
+          "FAILED open or read"
+        }
+        Err(error) => return Err(error.into()),
+      };
+      files.push(Value::record(
+        record! {
+          "path" => Value::string(path, head),
+          "algorithm" => Value::string(algorithm.name, head),
+          "status" => Value::string(status, head),
+        },
+        head,
+      ));
+    }
+
+    let success = formatted > 0
+      && failed == 0
+      && (missing == 0 || ignore_missing)
+      && (!ignore_missing || ok > 0)
+      && (!strict || improperly_formatted == 0);
+    Ok(
+      Value::record(
+        record! {
+          "files" => Value::list(files, head),
+          "summary" => Value::record(
+            record! {
+              "ok" => Value::int(ok, head),
+              "failed" => Value::int(failed, head),
+              "missing" => Value::int(missing, head),
+              "improperly_formatted" => Value::int(improperly_formatted, head),
+            },
+            head,
+          ),
+          "success" => Value::bool(success, head),
+        },
+        head,
+      )
+      .into_pipeline_data(),
+    )
+  }
+}
+
+/// Collects string or binary input into a string.
+pub fn input_text(input: PipelineData) -> Result<String, ShellError> {
+  match input {
+    PipelineData::ByteStream(stream, ..) => stream.into_string(),
+    PipelineData::Value(Value::String { val, .. }, ..) => Ok(val),
+    PipelineData::Value(Value::Binary { val, internal_span }, ..) => {
+      String::from_utf8(val).map_err(|_| ShellError::NonUtf8 {
+        span: internal_span,
+      })
+    }
+    PipelineData::Value(other, ..) => {
+      Err(ShellError::OnlySupportsThisInputType {
+        exp_input_type: "string or binary".into(),
+        wrong_type: other.get_type().to_string(),
+        dst_span: other.span(),
+        src_span: other.span(),
+      })
+    }
+    PipelineData::ListStream(stream, ..) => {
+      Err(ShellError::OnlySupportsThisInputType {
+        exp_input_type: "string or binary".into(),
+        wrong_type: "list".into(),
+        dst_span: stream.span(),
+        src_span: stream.span(),
+      })
+    }
+    PipelineData::Empty => Err(ShellError::PipelineEmpty {
+      dst_span: nu_protocol::Span::unknown(),
+    }),
+  }
+}
+
+#[cfg(all(test, feature = "sha2", feature = "sha3"))]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::Span;
+
+  use super::*;
+
+  #[test]
+  fn test_check() -> Result<(), ShellError> {
+    let dir = crate::file::TestDir::new("check");
+    std::fs::write(dir.join("a.txt"), "abcdefghijklmnopqrstuvwxyz").unwrap();
+    std::fs::write(dir.join("b.txt"), "wrong").unwrap();
+    let sums = format!(
+      "# comment\n\
+       4dbff86cc2ca1bae1e16468a05cb9881c97f1753bce3619034898faa1aabe429\
+       955a1bf8ec483d7421fe3c1646613a59ed5441fb0f321389f77f48a879c7b1f1  {a}\n\
+       SHA3-256 ({a}) = \
+       7cab2dc765e21b241dbc1c255ce620b29f527c6d5e7f5f843e56288f0d707521\n\
+       SHA3-256 ({b}) = \
+       7cab2dc765e21b241dbc1c255ce620b29f527c6d5e7f5f843e56288f0d707521\n\
+       SHA3-256 ({c}) = \
+       7cab2dc765e21b241dbc1c255ce620b29f527c6d5e7f5f843e56288f0d707521\n\
+       not a checksum line\n",
+      a = dir.join("a.txt").display(),
+      b = dir.join("b.txt").display(),
+      c = dir.join("c.txt").display(),
+    );
+
+    let mut plugin_test = PluginTest::new("hash check", HashesPlugin.into())?;
+    let mut check = |args: &str| -> Result<Value, ShellError> {
+      plugin_test
+        .eval_with(
+          &format!("hash check --algorithm sha512 {args}"),
+          Value::test_string(&sums).into_pipeline_data(),
+        )?
+        .into_value(Span::test_data())
+    };
+
+    let result = check("")?;
+    let statuses: Vec<String> = result
+      .get_data_by_key("files")
+      .unwrap()
+      .into_list()?
+      .into_iter()
+      .map(|row| row.get_data_by_key("status").unwrap().into_string())
+      .collect::<Result<_, _>>()?;
+    assert_eq!(statuses, ["ok", "ok", "FAILED", "missing"]);
+    let summary = result.get_data_by_key("summary").unwrap();
+    assert_eq!(
+      summary.get_data_by_key("improperly_formatted"),
+      Some(Value::test_int(1))
+    );
+    assert_eq!(
+      result.get_data_by_key("success"),
+      Some(Value::test_bool(false))
+    );
+
+    let result = check("--ignore-missing")?;
+    assert_eq!(
+      result.get_data_by_key("files").unwrap().into_list()?.len(),
+      3
+    );
+    Ok(())
+  }
+}
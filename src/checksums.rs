@@ -0,0 +1,208 @@
+//! Contains parsing and writing of checksum files in formats of GNU coreutils,
+//! like `<hex>  <path>`, and of BSD tags, like `SHA3-256 (path) = <hex>`.
+
+use crate::registry::{self, Algorithm};
+
+/// A properly formatted line of a checksum file.
+#[derive(Debug, PartialEq)]
+pub struct ChecksumLine {
+  /// Algorithm name of a BSD tag line, `None` for GNU lines.
+  pub tag: Option<String>,
+  /// Hex-encoded digest.
+  pub digest: String,
+  pub path: String,
+}
+
+/// Parses a line of a checksum file, returning `None` if it's not properly
+/// formatted.
+pub fn parse_line(line: &str) -> Option<ChecksumLine> {
+  let line = line.strip_suffix('\r').unwrap_or(line);
+  // A leading backslash means that the file name is escaped
+  let (escaped, line) = match line.strip_prefix('\\') {
+    Some(line) => (true, line),
+    None => (false, line),
+  };
+  let (tag, digest, path) =
+    parse_bsd_line(line).or_else(|| parse_gnu_line(line))?;
+  if digest.is_empty() || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+    return None;
+  }
+  let path = if escaped {
+    unescape(path)?
+  } else {
+    path.to_owned()
+  };
+  if path.is_empty() {
+    return None;
+  }
+  Some(ChecksumLine {
+    tag: tag.map(ToOwned::to_owned),
+    digest: digest.to_ascii_lowercase(),
+    path,
+  })
+}
+
+/// Parses `<digest> <space or asterisk><path>`.
+fn parse_gnu_line(line: &str) -> Option<(Option<&str>, &str, &str)> {
+  let (digest, rest) = line.split_once(' ')?;
+  let path = rest.strip_prefix([' ', '*'])?;
+  Some((None, digest, path))
+}
+
+/// Parses `<tag> (<path>) = <digest>`.
+fn parse_bsd_line(line: &str) -> Option<(Option<&str>, &str, &str)> {
+  let (tag, rest) = line.split_once(" (")?;
+  let (path, digest) = rest.rsplit_once(") = ")?;
+  if tag.is_empty() || tag.contains(char::is_whitespace) {
+    return None;
+  }
+  Some((Some(tag), digest, path))
+}
+
+/// Reverts escaping of a file name: `\\` to a backslash, `\n` to a new line
+/// and `\r` to a carriage return.
+fn unescape(path: &str) -> Option<String> {
+  let mut unescaped = String::with_capacity(path.len());
+  let mut chars = path.chars();
+  while let Some(c) = chars.next() {
+    if c == '\\' {
+      match chars.next()? {
+        '\\' => unescaped.push('\\'),
+        'n' => unescaped.push('\n'),
+        'r' => unescaped.push('\r'),
+        _ => return None,
+      }
+    } else {
+      unescaped.push(c);
+    }
+  }
+  Some(unescaped)
+}
+
+/// Formats a line in GNU format, escaping the path if needed.
+pub fn gnu_line(digest: &str, path: &str) -> String {
+  match escape(path) {
+    Some(path) => format!("\\{digest}  {path}"),
+    None => format!("{digest}  {path}"),
+  }
+}
+
+/// Formats a line in BSD tag format, escaping the path if needed.
+pub fn bsd_line(algorithm: &Algorithm, digest: &str, path: &str) -> String {
+  let tag = tag(algorithm);
+  match escape(path) {
+    Some(path) => format!("\\{tag} ({path}) = {digest}"),
+    None => format!("{tag} ({path}) = {digest}"),
+  }
+}
+
+/// Escapes a file name the way coreutils do, returning `None` if it doesn't
+/// need escaping.
+fn escape(path: &str) -> Option<String> {
+  if !path.contains(['\\', '\n', '\r']) {
+    return None;
+  }
+  Some(
+    path
+      .replace('\\', "\\\\")
+      .replace('\n', "\\n")
+      .replace('\r', "\\r"),
+  )
+}
+
+/// Returns the name of an algorithm used in BSD tags. It's the same name
+/// coreutils and BSD tools use, if they implement the algorithm.
+pub fn tag(algorithm: &Algorithm) -> String {
+  match algorithm.name {
+    "blake2b-512" => "BLAKE2b".into(),
+    "blake2s-256" => "BLAKE2s".into(),
+    "sha512-224" => "SHA512t224".into(),
+    "sha512-256" => "SHA512t256".into(),
+    "skein256-32" => "Skein256".into(),
+    "skein512-64" => "Skein512".into(),
+    "skein1024-128" => "Skein1024".into(),
+    name => match name.strip_prefix("ripemd") {
+      Some(bits) => format!("RMD{bits}"),
+      None => name.to_ascii_uppercase(),
+    },
+  }
+}
+
+/// Returns an algorithm by the name used in BSD tags, like `SHA512`,
+/// `SHA3-256`, `BLAKE2b` or `RMD160`.
+pub fn algorithm_by_tag(tag: &str) -> Option<&'static Algorithm> {
+  let name = tag.to_ascii_lowercase().replace('/', "-");
+  let name = match name.as_str() {
+    "blake2b" => "blake2b-512",
+    "blake2s" => "blake2s-256",
+    "rmd128" => "ripemd128",
+    "rmd160" => "ripemd160",
+    "rmd256" => "ripemd256",
+    "rmd320" => "ripemd320",
+    "sha512t224" => "sha512-224",
+    "sha512t256" => "sha512-256",
+    "skein256" => "skein256-32",
+    "skein512" => "skein512-64",
+    "skein1024" => "skein1024-128",
+    name => name,
+  };
+  registry::get(name)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_line() {
+    assert_eq!(
+      parse_line("0123abcd  file name.txt"),
+      Some(ChecksumLine {
+        tag: None,
+        digest: "0123abcd".into(),
+        path: "file name.txt".into(),
+      })
+    );
+    assert_eq!(
+      parse_line("0123ABCD *dir/file.bin\r"),
+      Some(ChecksumLine {
+        tag: None,
+        digest: "0123abcd".into(),
+        path: "dir/file.bin".into(),
+      })
+    );
+    assert_eq!(
+      parse_line("\\0123abcd  new\\nline\\\\back"),
+      Some(ChecksumLine {
+        tag: None,
+        digest: "0123abcd".into(),
+        path: "new\nline\\back".into(),
+      })
+    );
+    assert_eq!(
+      parse_line("SHA3-256 (a (b) = c) = 0123abcd"),
+      Some(ChecksumLine {
+        tag: Some("SHA3-256".into()),
+        digest: "0123abcd".into(),
+        path: "a (b) = c".into(),
+      })
+    );
+    assert_eq!(parse_line("0123abcd file"), None);
+    assert_eq!(parse_line("xyz  file"), None);
+    assert_eq!(parse_line("0123abcd  "), None);
+    assert_eq!(parse_line("\\0123abcd  bad\\escape"), None);
+  }
+
+  #[test]
+  fn test_write_line() {
+    let path = "new\nline\\back";
+    let line = parse_line(&gnu_line("0123abcd", path)).unwrap();
+    assert_eq!(line.path, path);
+    for algorithm in registry::ALGORITHMS {
+      let line = parse_line(&bsd_line(algorithm, "0123abcd", path)).unwrap();
+      assert_eq!(line.path, path);
+      let tag = line.tag.unwrap();
+      assert_eq!(algorithm_by_tag(&tag).map(|a| a.name), Some(algorithm.name));
+    }
+  }
+}
@@ -0,0 +1,175 @@
+//! Contains a command that computes content identifiers of IPFS for raw
+//! content. It needs the sha2 feature, as IPFS uses SHA-256 by default.
+
+use data_encoding::BASE32_NOPAD;
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  Signature,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+
+use crate::{
+  hasher::DynHasher,
+  multi::digest_input,
+  multihash,
+  registry,
+  HashesPlugin,
+};
+
+/// The algorithm IPFS uses by default.
+const DEFAULT_ALGORITHM: &str = "sha256";
+
+pub struct Cid;
+
+impl PluginCommand for Cid {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash cid"
+  }
+
+  fn signature(&self) -> Signature {
+    Signature::build(self.name())
+      .category(Category::Hash)
+      .input_output_types(vec![
+        (Type::Binary, Type::String),
+        (Type::String, Type::String),
+      ])
+      .named(
+        "algorithm",
+        SyntaxShape::String,
+        "Hash algorithm with a multicodec code, sha256 by default",
+        Some('a'),
+      )
+      .named(
+        "base",
+        SyntaxShape::String,
+        "Multibase of the output: base32 (default) or base58btc",
+        None,
+      )
+  }
+
+  fn description(&self) -> &str {
+    "Compute a version 1 CID of raw content."
+  }
+
+  fn extra_description(&self) -> &str {
+    "The CID uses the raw codec, so it matches the CID of a file added to IPFS \
+     with `ipfs add --cid-version 1 --raw-leaves`, if it fits in a single \
+     block. Only algorithms that have a multicodec code are accepted, they are \
+     listed by `hash algorithms`."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Compute the CID of a string",
+        example: "'hello world' | hash cid",
+        result: Some(Value::test_string(
+          "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e",
+        )),
+      },
+      Example {
+        description: "Compute the CID of a string with sha512",
+        example: "'abcdefghijklmnopqrstuvwxyz' | hash cid --algorithm sha512",
+        result: Some(Value::test_string(
+          "bafkrgqcnx74gzqwkdoxb4fsgric4xgebzf7rou544nqzanejr6vbvk7efgkvug7y5re\
+           d25bb7y6bmrtbhjm62vcb7mhtee4j657urkdzy6y7c",
+        )),
+      },
+      Example {
+        description: "Compute the CID of a string in base58btc",
+        example: "'abcdefghijklmnopqrstuvwxyz' | hash cid --base base58btc",
+        result: Some(Value::test_string(
+          "zb2rheJP3xD9A3QycUsb33BxCzHXitBNQtZgffiZpoRJjHABk",
+        )),
+      },
+      Example {
+        description: "Compute the CID of a file's contents with blake3",
+        example: "open --raw ./nu_0_24_1_windows.zip | hash cid --algorithm \
+                  blake3",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let name = call
+      .get_flag::<Spanned<String>>("algorithm")?
+      .unwrap_or_else(|| Spanned {
+        item: DEFAULT_ALGORITHM.into(),
+        span: head,
+      });
+    let (code, hasher) = hasher(&name)?;
+    let base58 = match call.get_flag::<Spanned<String>>("base")? {
+      None => false,
+      Some(base) => match base.item.as_str() {
+        "base32" => false,
+        "base58btc" => true,
+        _ => {
+          return Err(
+            LabeledError::new(format!("Unknown multibase `{}`", base.item))
+              .with_label("multibase not found", base.span)
+              .with_help("available multibases are: base32, base58btc"),
+          )
+        }
+      },
+    };
+
+    let digest = digest_input(input, vec![hasher], head)?.remove(0);
+    let cid = multihash::cid_v1(multihash::encode(code, digest));
+    let cid = if base58 {
+      format!("z{}", bs58::encode(&cid).into_string())
+    } else {
+      format!("b{}", BASE32_NOPAD.encode(&cid).to_ascii_lowercase())
+    };
+    Ok(Value::string(cid, head).into_pipeline_data())
+  }
+}
+
+/// Returns the multicodec code and a hasher of an algorithm. SHA-256 has no
+/// command of its own, as Nushell has one, so it's not in the registry.
+fn hasher(
+  name: &Spanned<String>,
+) -> Result<(u64, Box<dyn DynHasher>), LabeledError> {
+  if name.item == "sha256" {
+    return Ok((0x12, registry::new_hasher::<sha2::Sha256>()));
+  }
+  let algorithm = registry::find(name)?;
+  match algorithm.multicodec {
+    Some(code) => Ok((code, (algorithm.new)())),
+    None => Err(
+      LabeledError::new(format!("{} has no multihash code", algorithm.name))
+        .with_label("this algorithm isn't in the multicodec table", name.span),
+    ),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::ShellError;
+
+  use super::*;
+
+  #[test]
+  fn test_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash cid", HashesPlugin.into())?
+      .test_examples(&Cid.examples())
+  }
+}
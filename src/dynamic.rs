@@ -0,0 +1,253 @@
+//! Contains a command that hashes with an algorithm selected at runtime from
+//! the registry. It behaves exactly like `GenericHasher` commands do.
+
+use std::ops::Not;
+
+use nu_cmd_base::input_handler::CmdArgument;
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  ast::CellPath,
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+
+use crate::{
+  encoding::{self, Encoding},
+  hasher::{hash_value, DynDigestWriter, DynHasher},
+  multihash,
+  parallel,
+  registry::{self, Algorithm},
+  HashesPlugin,
+};
+
+pub struct DynamicHasher;
+
+struct Arguments {
+  cell_paths: Option<Vec<CellPath>>,
+  encoding: Encoding,
+  mac: Option<Box<dyn DynHasher>>,
+  multihash: Option<u64>,
+  algorithm: &'static Algorithm,
+}
+
+impl CmdArgument for Arguments {
+  fn take_cell_paths(&mut self) -> Option<Vec<CellPath>> {
+    self.cell_paths.take()
+  }
+}
+
+impl PluginCommand for DynamicHasher {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash digest"
+  }
+
+  fn signature(&self) -> Signature {
+    parallel::signature(multihash::signature(encoding::signature(
+      Signature::build(self.name())
+        .category(Category::Hash)
+        .input_output_types(vec![
+          (Type::Binary, Type::Any),
+          (Type::String, Type::Any),
+          (Type::table(), Type::table()),
+          (Type::record(), Type::record()),
+        ])
+        .allow_variants_without_examples(true)
+        .required_named(
+          "algorithm",
+          SyntaxShape::String,
+          "Name of the hash algorithm, same as the name of its command",
+          Some('a'),
+        ),
+    )))
+    .named(
+      "key",
+      SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+      "Compute HMAC keyed with the given string or binary, or a keyed \
+       BLAKE3 hash with a 32-byte key",
+      Some('k'),
+    )
+    .rest(
+      "rest",
+      SyntaxShape::CellPath,
+      "Optionally hash data by cell path.",
+    )
+  }
+
+  fn description(&self) -> &str {
+    "Hash a value using the hash algorithm selected by name."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Return the sha512 hash of a string, hex-encoded",
+        example:
+          "'abcdefghijklmnopqrstuvwxyz' | hash digest --algorithm sha512",
+        result: Some(Value::string(
+          "4dbff86cc2ca1bae1e16468a05cb9881c97f1753bce3619034898faa1aabe429\
+           955a1bf8ec483d7421fe3c1646613a59ed5441fb0f321389f77f48a879c7b1f1",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the HMAC-sha512 of a string, keyed with a string",
+        example: "'what do ya want for nothing?' | hash digest --algorithm \
+                  sha512 --key 'Jefe'",
+        result: Some(Value::string(
+          "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+           9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the sha512 multihash of a string, hex-encoded",
+        example: "'abcdefghijklmnopqrstuvwxyz' | hash digest --algorithm \
+                  sha512 --format multihash",
+        result: Some(Value::string(
+          "13404dbff86cc2ca1bae1e16468a05cb9881c97f1753bce3619034898faa1aabe4\
+           29955a1bf8ec483d7421fe3c1646613a59ed5441fb0f321389f77f48a879c7b1f1",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Hash a file's contents with an algorithm from a variable",
+        example: "let alg = 'sha3-256'; open ./nu_0_24_1_windows.zip | hash \
+                  digest --algorithm $alg",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let algorithm: Spanned<String> =
+      call.get_flag("algorithm")?.ok_or_else(|| {
+        LabeledError::new("Missing algorithm").with_label("", head)
+      })?;
+    let algorithm = registry::find(&algorithm)?;
+    let encoding = Encoding::from_call(call)?;
+    let key: Option<Spanned<Vec<u8>>> = call.get_flag("key")?;
+    let multihash =
+      multihash::from_call(call, algorithm.name, algorithm.multicodec)?;
+    let cell_paths: Vec<CellPath> = call.rest(0)?;
+    let cell_paths = cell_paths.is_empty().not().then_some(cell_paths);
+
+    let mac = key.map(|key| algorithm.new_keyed(&key)).transpose()?;
+
+    if let PipelineData::ByteStream(stream, ..) = input {
+      let mut hasher = mac.unwrap_or_else(algorithm.new);
+      stream.write_to(&mut DynDigestWriter(hasher.as_mut()))?;
+      let digest = hasher.finalize().into_vec();
+      let digest = match multihash {
+        Some(code) => multihash::encode(code, digest),
+        None => digest,
+      };
+      Ok(encoding.encode(digest, head).into_pipeline_data())
+    } else {
+      parallel::operate(
+        action,
+        Arguments {
+          cell_paths,
+          encoding,
+          mac,
+          multihash,
+          algorithm,
+        },
+        input,
+        head,
+        engine.signals(),
+        parallel::from_call(engine, call)?,
+      )
+      .map_err(Into::into)
+    }
+  }
+}
+
+fn action(input: &Value, args: &Arguments, _span: Span) -> Value {
+  hash_value(input, args.encoding, |bytes| {
+    let mut hasher = match &args.mac {
+      Some(mac) => mac.fork(),
+      None => (args.algorithm.new)(),
+    };
+    hasher.update(bytes);
+    let digest = hasher.finalize().into_vec();
+    match args.multihash {
+      Some(code) => multihash::encode(code, digest),
+      None => digest,
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::{ByteStream, ShellError, Signals};
+
+  use super::*;
+
+  #[cfg(feature = "sha2")]
+  #[test]
+  fn test_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash digest", HashesPlugin.into())?
+      .test_examples(&DynamicHasher.examples())
+  }
+
+  #[test]
+  fn test_same_as_algorithm_commands() -> Result<(), ShellError> {
+    let mut plugin_test = PluginTest::new("hash digest", HashesPlugin.into())?;
+    let mut eval = |source: &str, stream: bool| {
+      let input = if stream {
+        PipelineData::ByteStream(
+          ByteStream::read_binary(
+            b"abcdefghijklmnopqrstuvwxyz".to_vec(),
+            Span::test_data(),
+            Signals::empty(),
+          ),
+          None,
+        )
+      } else {
+        PipelineData::empty()
+      };
+      plugin_test
+        .eval_with(source, input)?
+        .into_value(Span::test_data())
+    };
+    for algorithm in registry::ALGORITHMS {
+      let name = algorithm.name;
+      for (input, args, stream) in [
+        ("'abcdefghijklmnopqrstuvwxyz' | ", "", false),
+        (
+          "{a: 'abc', b: 0x[00ff], c: 'def'} | ",
+          "a b --binary",
+          false,
+        ),
+        ("", "", true),
+      ] {
+        let expected = eval(&format!("{input}hash {name} {args}"), stream)?;
+        let actual = eval(
+          &format!("{input}hash digest --algorithm {name} {args}"),
+          stream,
+        )?;
+        assert_eq!(expected, actual, "{name}");
+      }
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,202 @@
+//! Contains encodings of digests, which are selected with the `--encoding`
+//! and `--binary` flags shared by hashing commands.
+
+use data_encoding::{
+  Encoding as DataEncoding,
+  BASE32,
+  BASE32_NOPAD,
+  BASE64,
+  BASE64URL,
+  BASE64URL_NOPAD,
+  BASE64_NOPAD,
+  HEXLOWER,
+  HEXLOWER_PERMISSIVE,
+  HEXUPPER,
+};
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{LabeledError, Signature, Span, Spanned, SyntaxShape, Value};
+
+use crate::nix32;
+
+/// Names of encodings accepted by `--encoding`.
+const NAMES: &[&str] = &[
+  "hex",
+  "hex-upper",
+  "base32",
+  "nix32",
+  "base58",
+  "base64",
+  "base64url",
+];
+
+#[derive(Clone, Copy, Default)]
+pub enum Encoding {
+  Binary,
+  /// Lowercase hexadecimal.
+  #[default]
+  Hex,
+  /// Uppercase hexadecimal.
+  HexUpper,
+  /// RFC 4648 base32, with padding.
+  Base32,
+  /// Base32 in the flavor used by Nix.
+  Nix32,
+  /// Base58 with the Bitcoin alphabet.
+  Base58,
+  /// RFC 4648 base64, with padding.
+  Base64,
+  /// RFC 4648 URL-safe base64, without padding.
+  Base64Url,
+}
+
+impl Encoding {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "hex" => Some(Self::Hex),
+      "hex-upper" => Some(Self::HexUpper),
+      "base32" => Some(Self::Base32),
+      "nix32" => Some(Self::Nix32),
+      "base58" => Some(Self::Base58),
+      "base64" => Some(Self::Base64),
+      "base64url" => Some(Self::Base64Url),
+      _ => None,
+    }
+  }
+
+  /// Returns an encoding by its name, or an error pointing at the name if
+  /// there's no such encoding.
+  pub fn find(name: &Spanned<String>) -> Result<Self, LabeledError> {
+    Self::from_name(&name.item).ok_or_else(|| {
+      LabeledError::new(format!("Unknown encoding `{}`", name.item))
+        .with_label("encoding not found", name.span)
+        .with_help(format!("available encodings are: {}", NAMES.join(", ")))
+    })
+  }
+
+  /// Reads the `--binary` and `--encoding` flags of a call.
+  pub fn from_call(call: &EvaluatedCall) -> Result<Self, LabeledError> {
+    let binary = call.has_flag("binary")?;
+    let Some(name) = call.get_flag::<Spanned<String>>("encoding")? else {
+      return Ok(if binary { Self::Binary } else { Self::Hex });
+    };
+    if binary {
+      return Err(
+        LabeledError::new("Incompatible flags")
+          .with_label("can't be used with --binary", name.span),
+      );
+    }
+    Self::find(&name)
+  }
+
+  /// Returns a digest as binary or as an encoded string.
+  pub fn encode(self, digest: Vec<u8>, span: Span) -> Value {
+    let encoded = match self {
+      Self::Binary => return Value::binary(digest, span),
+      Self::Hex => HEXLOWER.encode(&digest),
+      Self::HexUpper => HEXUPPER.encode(&digest),
+      Self::Base32 => BASE32.encode(&digest),
+      Self::Nix32 => nix32::encode(&digest),
+      Self::Base58 => bs58::encode(&digest).into_string(),
+      Self::Base64 => BASE64.encode(&digest),
+      Self::Base64Url => BASE64URL_NOPAD.encode(&digest),
+    };
+    Value::string(encoded, span)
+  }
+
+  /// Decodes a string in this encoding. Padding is optional and hex digits
+  /// can be of any case.
+  pub fn decode(self, encoded: &str) -> Option<Vec<u8>> {
+    let either = |a: &DataEncoding, b: &DataEncoding| {
+      a.decode(encoded.as_bytes())
+        .or_else(|_| b.decode(encoded.as_bytes()))
+        .ok()
+    };
+    match self {
+      Self::Binary => None,
+      Self::Hex | Self::HexUpper => {
+        HEXLOWER_PERMISSIVE.decode(encoded.as_bytes()).ok()
+      }
+      Self::Base32 => either(&BASE32, &BASE32_NOPAD),
+      Self::Nix32 => nix32::decode(encoded),
+      Self::Base58 => bs58::decode(encoded).into_vec().ok(),
+      Self::Base64 => either(&BASE64, &BASE64_NOPAD),
+      Self::Base64Url => either(&BASE64URL_NOPAD, &BASE64URL),
+    }
+  }
+}
+
+/// Encodings, that `decode_any` tries in order.
+const DECODE_ORDER: &[Encoding] = &[
+  Encoding::Hex,
+  Encoding::Base64,
+  Encoding::Base64Url,
+  Encoding::Base32,
+  Encoding::Nix32,
+  Encoding::Base58,
+];
+
+/// Decodes a string in the first encoding, that decodes it into bytes
+/// accepted by `accept`.
+pub fn decode_any(
+  encoded: &str,
+  accept: impl Fn(&[u8]) -> bool,
+) -> Option<Vec<u8>> {
+  DECODE_ORDER
+    .iter()
+    .filter_map(|encoding| encoding.decode(encoded))
+    .find(|bytes| accept(bytes))
+}
+
+/// Adds the `--binary` and `--encoding` flags to a signature.
+pub fn signature(signature: Signature) -> Signature {
+  signature
+    .switch(
+      "binary",
+      "Output binary instead of hexadecimal representation",
+      Some('b'),
+    )
+    .named(
+      "encoding",
+      SyntaxShape::String,
+      format!("Encoding of the output: {}", NAMES.join(", ")),
+      Some('e'),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_nix32() {
+    // SHA-256 of an empty string, as printed by `nix hash convert`
+    let digest = HEXLOWER
+      .decode(
+        b"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+      )
+      .unwrap();
+    let encoded = nix32::encode(&digest);
+    assert_eq!(
+      encoded,
+      "0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73"
+    );
+    assert_eq!(nix32::decode(&encoded), Some(digest));
+    assert_eq!(nix32::decode("zz"), None);
+  }
+
+  #[test]
+  fn test_decode() {
+    // Short strings can be valid in several encodings, digests are long
+    // enough to tell encodings apart by the decoded length
+    let digest: Vec<u8> = (0xe0..=0xff).collect();
+    for encoding in DECODE_ORDER {
+      let encoded = encoding.encode(digest.clone(), Span::test_data());
+      let encoded = encoded.as_str().unwrap();
+      assert_eq!(encoding.decode(encoded).as_ref(), Some(&digest));
+      assert_eq!(
+        decode_any(encoded, |bytes| bytes.len() == digest.len()).as_ref(),
+        Some(&digest)
+      );
+    }
+  }
+}
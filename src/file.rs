@@ -0,0 +1,598 @@
+//! Contains a command that hashes files by their paths, so they don't have
+//! to be read by Nushell and sent to the plugin.
+
+use std::{
+  fs::File,
+  io::{ErrorKind, Read, Seek, SeekFrom},
+  path::{Path, PathBuf},
+};
+
+use digest::DynDigest;
+use memmap2::MmapOptions;
+use nu_glob::MatchOptions;
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  record,
+  shell_error::io::IoError,
+  Category,
+  Example,
+  IntoInterruptiblePipelineData,
+  LabeledError,
+  NuGlob,
+  PipelineData,
+  ShellError,
+  Signals,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+
+use crate::{
+  encoding::{self, Encoding},
+  hasher::DynHasher,
+  multihash,
+  parallel,
+  registry::{self, Algorithm},
+  HashesPlugin,
+};
+
+const BUFFER_SIZE: usize = 64 * 1024;
+/// Files smaller than this are read instead of being memory-mapped, as
+/// mapping a file costs more than a few reads.
+const MMAP_THRESHOLD: u64 = 1024 * 1024;
+/// Size of chunks of memory maps, that are hashed between checks for
+/// interruption.
+const MMAP_CHUNK_SIZE: usize = 1024 * 1024;
+
+pub struct FileHasher;
+
+impl PluginCommand for FileHasher {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash file"
+  }
+
+  fn signature(&self) -> Signature {
+    parallel::signature(multihash::signature(encoding::signature(
+      Signature::build(self.name())
+        .category(Category::Hash)
+        .input_output_types(vec![
+          (Type::Nothing, Type::table()),
+          (Type::String, Type::table()),
+          (Type::list(Type::String), Type::table()),
+          (Type::table(), Type::table()),
+        ])
+        .required_named(
+          "algorithm",
+          SyntaxShape::String,
+          "Name of the hash algorithm, same as the name of its command",
+          Some('a'),
+        ),
+    )))
+    .named(
+      "key",
+      SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+      "Compute HMAC keyed with the given string or binary, or a keyed \
+       BLAKE3 hash with a 32-byte key",
+      Some('k'),
+    )
+    .rest(
+      "paths",
+      SyntaxShape::OneOf(vec![SyntaxShape::GlobPattern, SyntaxShape::String]),
+      "Paths or glob patterns of files to hash.",
+    )
+  }
+
+  fn description(&self) -> &str {
+    "Hash files by their paths using the hash algorithm selected by name."
+  }
+
+  fn extra_description(&self) -> &str {
+    "Paths can be given as arguments, piped in as strings or as a table with \
+     a `name` column, like the one `ls` returns. Files are read by the plugin \
+     itself. Files that can't be read produce error rows.
+
+Large regular files are memory-mapped, other files are read in chunks. \
+     Files that change size while they're mapped are read again."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Hash a file with sha512",
+        example: "hash file --algorithm sha512 nu_0_24_1_windows.zip",
+        result: None,
+      },
+      Example {
+        description: "Hash files matching a glob pattern",
+        example: "hash file --algorithm sha3-256 *.zip",
+        result: None,
+      },
+      Example {
+        description: "Hash files listed by ls",
+        example: "ls | where type == file | hash file --algorithm blake2b-512",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let algorithm: Spanned<String> =
+      call.get_flag("algorithm")?.ok_or_else(|| {
+        LabeledError::new("Missing algorithm").with_label("", head)
+      })?;
+    let algorithm = registry::find(&algorithm)?;
+    let encoding = Encoding::from_call(call)?;
+    let key: Option<Spanned<Vec<u8>>> = call.get_flag("key")?;
+    let multihash =
+      multihash::from_call(call, algorithm.name, algorithm.multicodec)?;
+    let mac = key.map(|key| algorithm.new_keyed(&key)).transpose()?;
+
+    let mut patterns: Vec<Spanned<NuGlob>> = call.rest(0)?;
+    patterns.extend(input_paths(input)?);
+    // The current directory is only needed to resolve relative paths
+    let cwd = if patterns
+      .iter()
+      .all(|pattern| Path::new(pattern.item.as_ref()).is_absolute())
+    {
+      PathBuf::new()
+    } else {
+      PathBuf::from(engine.get_current_dir()?)
+    };
+    let signals = engine.signals().clone();
+    let files = expand_patterns(patterns, &cwd, &signals);
+
+    let threads = parallel::from_call(engine, call)?;
+
+    let hash = {
+      let signals = signals.clone();
+      move |file: Result<FileTarget, ShellError>| {
+        let file = match file {
+          Ok(file) => file,
+          Err(error) => return Value::error(error, head),
+        };
+        let mac = mac.as_deref();
+        match digest_file(algorithm, mac, &file.path, &signals, file.span) {
+          Ok((size, digest)) => {
+            let digest = match multihash {
+              Some(code) => multihash::encode(code, digest),
+              None => digest,
+            };
+            file_row(&file, size, encoding.encode(digest, file.span))
+          }
+          Err(error) => Value::error(error, file.span),
+        }
+      }
+    };
+    Ok(match threads {
+      Some(threads) => {
+        parallel::stream(files.into_iter(), threads, signals, head, hash)
+      }
+      None => files
+        .into_iter()
+        .map(hash)
+        .into_pipeline_data(head, signals),
+    })
+  }
+}
+
+/// A file to hash.
+pub struct FileTarget {
+  /// Path to show in the output.
+  pub name: String,
+  /// Absolute path of the file.
+  pub path: PathBuf,
+  pub span: Span,
+}
+
+fn file_row(file: &FileTarget, size: u64, digest: Value) -> Value {
+  Value::record(
+    record! {
+      "path" => Value::string(&file.name, file.span),
+      "size" => Value::filesize(size as i64, file.span),
+      "digest" => digest,
+    },
+    file.span,
+  )
+}
+
+/// Collects paths from the pipeline input: strings, lists of strings or
+/// tables with a `name` column.
+pub fn input_paths(
+  input: PipelineData,
+) -> Result<Vec<Spanned<NuGlob>>, ShellError> {
+  let mut paths = Vec::new();
+  for value in input {
+    let span = value.span();
+    let path = match value {
+      Value::String { val, .. } => val,
+      Value::Record { val, .. } => match val.get("name") {
+        Some(Value::String { val, .. }) => val.clone(),
+        _ => {
+          return Err(ShellError::CantFindColumn {
+            col_name: "name".into(),
+            span: None,
+            src_span: span,
+          })
+        }
+      },
+      Value::Nothing { .. } => continue,
+      // Propagate existing errors
+      Value::Error { error, .. } => return Err(*error),
+      other => {
+        return Err(ShellError::OnlySupportsThisInputType {
+          exp_input_type: "string, list of strings or table".into(),
+          wrong_type: other.get_type().to_string(),
+          dst_span: span,
+          src_span: other.span(),
+        })
+      }
+    };
+    paths.push(Spanned {
+      item: NuGlob::DoNotExpand(path),
+      span,
+    });
+  }
+  Ok(paths)
+}
+
+/// Expands glob patterns into files. Patterns without matches produce errors.
+pub fn expand_patterns(
+  patterns: Vec<Spanned<NuGlob>>,
+  cwd: &Path,
+  signals: &Signals,
+) -> Vec<Result<FileTarget, ShellError>> {
+  let mut files = Vec::new();
+  for pattern in patterns {
+    let span = pattern.span;
+    let pattern = pattern.item;
+    if !pattern.is_expand() || !nu_glob::is_glob(pattern.as_ref()) {
+      files.push(Ok(FileTarget {
+        name: pattern.as_ref().to_owned(),
+        path: cwd.join(pattern.as_ref()),
+        span,
+      }));
+      continue;
+    }
+
+    let paths = match nu_glob::glob_with_parent(
+      pattern.as_ref(),
+      MatchOptions::default(),
+      cwd,
+      signals,
+    ) {
+      Ok(paths) => paths,
+      Err(error) => {
+        files.push(Err(ShellError::GenericError {
+          error: "Invalid glob pattern".into(),
+          msg: error.to_string(),
+          span: Some(span),
+          help: None,
+          inner: vec![],
+        }));
+        continue;
+      }
+    };
+    let count = files.len();
+    for path in paths {
+      files.push(
+        path
+          .map(|path| FileTarget {
+            name: path
+              .strip_prefix(cwd)
+              .unwrap_or(&path)
+              .to_string_lossy()
+              .into_owned(),
+            path,
+            span,
+          })
+          .map_err(|error| {
+            let path = error.path().to_path_buf();
+            IoError::new(error.into_error(), span, path).into()
+          }),
+      );
+    }
+    if files.len() == count {
+      files.push(Err(ShellError::GenericError {
+        error: "No matches found".into(),
+        msg: format!("no files match `{}`", pattern.as_ref()),
+        span: Some(span),
+        help: None,
+        inner: vec![],
+      }));
+    }
+  }
+  files
+}
+
+/// Hashes a file with `algorithm`, or with a copy of `mac` if it's given.
+/// Returns the number of bytes read and the digest.
+pub fn digest_file(
+  algorithm: &Algorithm,
+  mac: Option<&dyn DynHasher>,
+  path: &Path,
+  signals: &Signals,
+  span: Span,
+) -> Result<(u64, Vec<u8>), ShellError> {
+  #[cfg(feature = "blake3-rayon")]
+  if algorithm.name == "blake3" && mac.is_none() {
+    if let Some(result) = crate::xof::blake3_file(path, signals, span)? {
+      return Ok(result);
+    }
+  }
+  let mut hasher = match mac {
+    Some(mac) => mac.fork(),
+    None => (algorithm.new)(),
+  };
+  let size = hash_file(path, &mut hasher, 0, u64::MAX, signals, span)?;
+  Ok((size, hasher.finalize().into_vec()))
+}
+
+/// Feeds at most `length` bytes of a file, starting at `offset`, into
+/// `hasher`. Large ranges of regular files are memory-mapped, other files are
+/// read in chunks. Returns the number of bytes hashed.
+pub fn hash_file(
+  path: &Path,
+  hasher: &mut Box<dyn DynHasher>,
+  offset: u64,
+  length: u64,
+  signals: &Signals,
+  span: Span,
+) -> Result<u64, ShellError> {
+  let io_error = |error| IoError::new(error, span, path.to_path_buf());
+  let mut file = File::open(path).map_err(io_error)?;
+  // A map, that fails, leaves the hasher as it was, so the file can be read
+  let mut mapped = hasher.fork();
+  let update = &mut |chunk: &[u8]| mapped.update(chunk);
+  if let Some(size) = hash_mapped(&file, offset, length, update, signals, span)?
+  {
+    *hasher = mapped;
+    return Ok(size);
+  }
+
+  // Pipes can't seek, even to the start
+  if offset > 0 {
+    file.seek(SeekFrom::Start(offset)).map_err(io_error)?;
+  }
+  hash_reader(&mut file.take(length), hasher.as_mut(), path, signals, span)
+}
+
+/// Feeds everything `reader` reads from a file at `path` into `hasher`.
+/// Returns the number of bytes hashed.
+pub fn hash_reader(
+  reader: &mut impl Read,
+  hasher: &mut dyn DynDigest,
+  path: &Path,
+  signals: &Signals,
+  span: Span,
+) -> Result<u64, ShellError> {
+  let mut buffer = vec![0; BUFFER_SIZE];
+  let mut size = 0;
+  loop {
+    signals.check(&span)?;
+    match reader.read(&mut buffer) {
+      Ok(0) => return Ok(size),
+      Ok(read) => {
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+      }
+      Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+      Err(error) => {
+        return Err(IoError::new(error, span, path.to_path_buf()).into())
+      }
+    }
+  }
+}
+
+/// Feeds a memory map of at most `length` bytes of a file, starting at
+/// `offset`, into `update`. Returns `None` if the file isn't a large regular
+/// file, can't be mapped or its size changed while it was hashed, then it has
+/// to be read instead.
+pub fn hash_mapped(
+  file: &File,
+  offset: u64,
+  length: u64,
+  update: &mut dyn FnMut(&[u8]),
+  signals: &Signals,
+  span: Span,
+) -> Result<Option<u64>, ShellError> {
+  let size = |file: &File| file.metadata().map(|metadata| metadata.len());
+  let Ok(metadata) = file.metadata() else {
+    return Ok(None);
+  };
+  let end = offset.saturating_add(length).min(metadata.len());
+  let Ok(length) = usize::try_from(end.saturating_sub(offset)) else {
+    return Ok(None);
+  };
+  if !metadata.is_file() || (length as u64) < MMAP_THRESHOLD {
+    return Ok(None);
+  }
+  // SAFETY: the map is only read while the file is open. Other processes
+  // can still modify the file, which changes the digest just like reading it
+  // would. Reading a part of the map past the end of a truncated file
+  // crashes the process with SIGBUS though, so the size of the file is
+  // checked before each chunk, and truncated files are read instead. Only a
+  // truncation while a chunk is hashed can't be caught.
+  let Ok(map) =
+    (unsafe { MmapOptions::new().offset(offset).len(length).map(file) })
+  else {
+    return Ok(None);
+  };
+  let mut hashed = 0;
+  for chunk in map.chunks(MMAP_CHUNK_SIZE) {
+    signals.check(&span)?;
+    hashed += chunk.len() as u64;
+    if !size(file).is_ok_and(|size| size >= offset + hashed) {
+      return Ok(None);
+    }
+    update(chunk);
+  }
+  match size(file) {
+    // Whole files, that changed size meanwhile, are read again
+    Ok(size) if size == metadata.len() || end < metadata.len() => {
+      Ok(Some(hashed))
+    }
+    _ => Ok(None),
+  }
+}
+
+/// A temporary directory of a test, removed when dropped. Each test gets a
+/// directory of its own, as tests of several processes may run at once. Only
+/// tests of the sha2 and blake3-rayon features write files.
+#[cfg(all(test, any(feature = "sha2", feature = "blake3-rayon")))]
+pub struct TestDir(PathBuf);
+
+#[cfg(all(test, any(feature = "sha2", feature = "blake3-rayon")))]
+impl TestDir {
+  pub fn new(name: &str) -> Self {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let path = std::env::temp_dir().join(format!(
+      "nu_plugin_hashes_test_{name}_{}_{}",
+      std::process::id(),
+      NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&path).unwrap();
+    Self(path)
+  }
+}
+
+#[cfg(all(test, any(feature = "sha2", feature = "blake3-rayon")))]
+impl std::ops::Deref for TestDir {
+  type Target = Path;
+
+  fn deref(&self) -> &Path {
+    &self.0
+  }
+}
+
+#[cfg(all(test, any(feature = "sha2", feature = "blake3-rayon")))]
+impl Drop for TestDir {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_dir_all(&self.0);
+  }
+}
+
+#[cfg(all(test, feature = "sha2"))]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+
+  use super::*;
+
+  #[test]
+  fn test_hash_files() -> Result<(), ShellError> {
+    let dir = TestDir::new("hash_files");
+    std::fs::write(dir.join("a.txt"), "abcdefghijklmnopqrstuvwxyz").unwrap();
+    std::fs::write(dir.join("b.txt"), "").unwrap();
+
+    let mut plugin_test = PluginTest::new("hash file", HashesPlugin.into())?;
+    let rows = plugin_test
+      .eval(&format!(
+        "hash file --algorithm sha512 `{}` `{}`",
+        dir.join("*.txt").display(),
+        dir.join("missing.txt").display(),
+      ))?
+      .into_value(Span::test_data())?
+      .into_list()?;
+
+    assert_eq!(rows.len(), 3);
+    let digest = |row: &Value| -> Result<String, ShellError> {
+      row
+        .as_record()?
+        .get("digest")
+        .unwrap()
+        .clone()
+        .into_string()
+    };
+    assert_eq!(
+      digest(&rows[0])?,
+      "4dbff86cc2ca1bae1e16468a05cb9881c97f1753bce3619034898faa1aabe429\
+       955a1bf8ec483d7421fe3c1646613a59ed5441fb0f321389f77f48a879c7b1f1"
+    );
+    assert_eq!(
+      digest(&rows[1])?,
+      "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce\
+       47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"
+    );
+    assert!(rows[2].is_error());
+    Ok(())
+  }
+
+  #[test]
+  fn test_hash_mapped() -> Result<(), ShellError> {
+    use sha2::Digest;
+
+    let dir = TestDir::new("hash_mapped");
+    let path = dir.join("data.bin");
+    // Large enough to be mapped, not a multiple of the chunk size
+    let data: Vec<u8> = (0..2 * MMAP_CHUNK_SIZE + 7).map(|i| i as u8).collect();
+    std::fs::write(&path, &data).unwrap();
+
+    let algorithm = registry::get("sha512").unwrap();
+    let (size, digest) = digest_file(
+      algorithm,
+      None,
+      &path,
+      &Signals::empty(),
+      Span::test_data(),
+    )?;
+    assert_eq!(size, data.len() as u64);
+    assert_eq!(digest, sha2::Sha512::digest(&data).to_vec());
+
+    let mut hasher = sha2::Sha512::new();
+    let size = hash_mapped(
+      &File::open(&path).unwrap(),
+      0,
+      u64::MAX,
+      &mut |chunk| Digest::update(&mut hasher, chunk),
+      &Signals::empty(),
+      Span::test_data(),
+    )?;
+    assert_eq!(size, Some(data.len() as u64));
+    assert_eq!(
+      hasher.finalize().to_vec(),
+      sha2::Sha512::digest(&data).to_vec()
+    );
+
+    // Ranges are mapped too
+    let mut hasher = (algorithm.new)();
+    let size = hash_file(
+      &path,
+      &mut hasher,
+      3,
+      2 * MMAP_CHUNK_SIZE as u64,
+      &Signals::empty(),
+      Span::test_data(),
+    )?;
+    assert_eq!(size, 2 * MMAP_CHUNK_SIZE as u64);
+    assert_eq!(
+      hasher.finalize().to_vec(),
+      sha2::Sha512::digest(&data[3..3 + 2 * MMAP_CHUNK_SIZE]).to_vec()
+    );
+
+    // Files truncated while they're mapped are read instead
+    let truncated = File::options().write(true).open(&path).unwrap();
+    let size = hash_mapped(
+      &File::open(&path).unwrap(),
+      0,
+      u64::MAX,
+      &mut |_| truncated.set_len(MMAP_CHUNK_SIZE as u64).unwrap(),
+      &Signals::empty(),
+      Span::test_data(),
+    )?;
+    assert_eq!(size, None);
+    Ok(())
+  }
+}
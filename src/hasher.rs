@@ -6,8 +6,16 @@
 
 use std::{io::Write, marker::PhantomData, ops::Not};
 
-use digest::{Digest, Output};
-use nu_cmd_base::input_handler::{operate, CmdArgument};
+use digest::{
+  core_api::BlockSizeUser,
+  Digest,
+  DynDigest,
+  FixedOutputReset,
+  KeyInit,
+  Reset,
+};
+use hmac::SimpleHmac;
+use nu_cmd_base::input_handler::CmdArgument;
 use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
 use nu_protocol::{
   ast::CellPath,
@@ -19,18 +27,78 @@ use nu_protocol::{
   ShellError,
   Signature,
   Span,
+  Spanned,
   SyntaxShape,
   Type,
   Value,
 };
 
-use crate::HashesPlugin;
+use crate::{
+  encoding::{self, Encoding},
+  multihash,
+  parallel,
+  registry,
+  HashesPlugin,
+};
 
 pub trait Hasher: Digest + Clone {
   fn name() -> &'static str;
+  /// Returns an HMAC instance over this algorithm keyed with `key`, or `None`
+  /// if HMAC is not defined for this algorithm.
+  fn hmac(key: &[u8]) -> Option<Box<dyn DynHasher>>;
   fn examples() -> Vec<Example<'static>>;
 }
 
+/// A type-erased hasher. Unlike `DynDigest`, it can be shared between threads,
+/// so hashers can be kept between calls of the plugin.
+pub trait DynHasher: DynDigest + Send + Sync {
+  /// Returns a copy of the hasher, that continues from the same state.
+  fn fork(&self) -> Box<dyn DynHasher>;
+  /// Returns the hasher as `Any`, so its concrete type can be recovered.
+  /// Only exported states need it.
+  #[cfg(any(feature = "sha1", feature = "sha2"))]
+  fn as_any(&self) -> &dyn std::any::Any;
+}
+
+impl<D> DynHasher for D
+where
+  D: DynDigest + Clone + Send + Sync + 'static,
+{
+  fn fork(&self) -> Box<dyn DynHasher> {
+    Box::new(self.clone())
+  }
+
+  #[cfg(any(feature = "sha1", feature = "sha2"))]
+  fn as_any(&self) -> &dyn std::any::Any {
+    self
+  }
+}
+
+/// Creates a type-erased HMAC instance over `H`.
+pub fn new_hmac<H>(key: &[u8]) -> Box<dyn DynHasher>
+where
+  H: Hasher + BlockSizeUser + FixedOutputReset + Reset + Send + Sync + 'static,
+{
+  Box::new(
+    SimpleHmac::<H>::new_from_slice(key)
+      .expect("HMAC accepts keys of any length"),
+  )
+}
+
+/// Adapts a type-erased hasher to `Write`, so it can consume a `ByteStream`.
+pub struct DynDigestWriter<'a>(pub &'a mut dyn DynDigest);
+
+impl Write for DynDigestWriter<'_> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.0.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
 #[derive(Clone)]
 pub struct GenericHasher<H: Hasher> {
   name: String,
@@ -53,7 +121,9 @@ impl<H: Hasher> Default for GenericHasher<H> {
 
 struct Arguments {
   cell_paths: Option<Vec<CellPath>>,
-  binary: bool,
+  encoding: Encoding,
+  key: Option<Vec<u8>>,
+  multihash: Option<u64>,
 }
 
 impl CmdArgument for Arguments {
@@ -65,7 +135,6 @@ impl CmdArgument for Arguments {
 impl<H> PluginCommand for GenericHasher<H>
 where
   H: Hasher + Write + Send + Sync + 'static,
-  Output<H>: core::fmt::LowerHex,
 {
   type Plugin = HashesPlugin;
 
@@ -74,25 +143,28 @@ where
   }
 
   fn signature(&self) -> Signature {
-    Signature::build(self.name())
-      .category(Category::Hash)
-      .input_output_types(vec![
-        (Type::Binary, Type::Any),
-        (Type::String, Type::Any),
-        (Type::table(), Type::table()),
-        (Type::record(), Type::record()),
-      ])
-      .allow_variants_without_examples(true)
-      .switch(
-        "binary",
-        "Output binary instead of hexadecimal representation",
-        Some('b'),
-      )
-      .rest(
-        "rest",
-        SyntaxShape::CellPath,
-        format!("Optionally {} hash data by cell path.", H::name()),
-      )
+    parallel::signature(multihash::signature(encoding::signature(
+      Signature::build(self.name())
+        .category(Category::Hash)
+        .input_output_types(vec![
+          (Type::Binary, Type::Any),
+          (Type::String, Type::Any),
+          (Type::table(), Type::table()),
+          (Type::record(), Type::record()),
+        ])
+        .allow_variants_without_examples(true),
+    )))
+    .named(
+      "key",
+      SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+      "Compute HMAC keyed with the given string or binary",
+      Some('k'),
+    )
+    .rest(
+      "rest",
+      SyntaxShape::CellPath,
+      format!("Optionally {} hash data by cell path.", H::name()),
+    )
   }
 
   fn description(&self) -> &str {
@@ -111,26 +183,53 @@ where
     input: PipelineData,
   ) -> Result<PipelineData, LabeledError> {
     let head = call.head;
-    let binary = call.has_flag("binary")?;
+    let encoding = Encoding::from_call(call)?;
+    let key: Option<Spanned<Vec<u8>>> = call.get_flag("key")?;
+    let multicodec = registry::get(H::name()).and_then(|a| a.multicodec);
+    let multihash = multihash::from_call(call, H::name(), multicodec)?;
     let cell_paths: Vec<CellPath> = call.rest(0)?;
     let cell_paths = cell_paths.is_empty().not().then_some(cell_paths);
 
+    let mac = key
+      .as_ref()
+      .map(|key| {
+        H::hmac(&key.item).ok_or_else(|| {
+          LabeledError::new(format!("HMAC is not supported by {}", H::name()))
+            .with_label("this algorithm can't be keyed", key.span)
+        })
+      })
+      .transpose()?;
+
     if let PipelineData::ByteStream(stream, ..) = input {
-      let mut hasher = H::new();
-      stream.write_to(&mut hasher)?;
-      let digest = hasher.finalize();
-      if binary {
-        Ok(Value::binary(digest.to_vec(), head).into_pipeline_data())
-      } else {
-        Ok(Value::string(format!("{digest:x}"), head).into_pipeline_data())
-      }
+      let digest = match mac {
+        Some(mut mac) => {
+          stream.write_to(&mut DynDigestWriter(mac.as_mut()))?;
+          mac.finalize().into_vec()
+        }
+        None => {
+          let mut hasher = H::new();
+          stream.write_to(&mut hasher)?;
+          hasher.finalize().to_vec()
+        }
+      };
+      let digest = match multihash {
+        Some(code) => multihash::encode(code, digest),
+        None => digest,
+      };
+      Ok(encoding.encode(digest, head).into_pipeline_data())
     } else {
-      operate(
+      parallel::operate(
         action::<H>,
-        Arguments { binary, cell_paths },
+        Arguments {
+          encoding,
+          cell_paths,
+          key: key.map(|key| key.item),
+          multihash,
+        },
         input,
         head,
         engine.signals(),
+        parallel::from_call(engine, call)?,
       )
       .map_err(Into::into)
     }
@@ -140,8 +239,36 @@ where
 fn action<H>(input: &Value, args: &Arguments, _span: Span) -> Value
 where
   H: Hasher,
-  Output<H>: core::fmt::LowerHex,
 {
+  hash_value(input, args.encoding, |bytes| {
+    let digest = match &args.key {
+      Some(key) => {
+        let mut mac = H::hmac(key).expect("HMAC support is checked in `run`");
+        mac.update(bytes);
+        mac.finalize().into_vec()
+      }
+      None => H::digest(bytes).to_vec(),
+    };
+    match args.multihash {
+      Some(code) => multihash::encode(code, digest),
+      None => digest,
+    }
+  })
+}
+
+/// Hashes a string or binary value with `hash`. Errors are propagated and
+/// values of other types are rejected.
+pub fn hash_value(
+  input: &Value,
+  encoding: Encoding,
+  hash: impl FnOnce(&[u8]) -> Vec<u8>,
+) -> Value {
+  map_bytes(input, |bytes, span| encoding.encode(hash(bytes), span))
+}
+
+/// Maps bytes of a string or binary value with `f`. Errors are propagated
+/// and values of other types are rejected.
+pub fn map_bytes(input: &Value, f: impl FnOnce(&[u8], Span) -> Value) -> Value {
   let span = input.span();
   let (bytes, span) = match input {
     Value::String { val, .. } => (val.as_bytes(), span),
@@ -163,11 +290,10 @@ where
     }
   };
 
-  let digest = H::digest(bytes);
+  f(bytes, span)
+}
 
-  if args.binary {
-    Value::binary(digest.to_vec(), span)
-  } else {
-    Value::string(format!("{digest:x}"), span)
-  }
+/// Formats bytes as a lowercase hexadecimal string.
+pub fn to_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{b:02x}")).collect()
 }
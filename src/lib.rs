@@ -1,8 +1,49 @@
+// `ShellError` is large, but it is what Nushell APIs return
+#![allow(clippy::result_large_err)]
+
+mod algorithms;
+#[cfg(feature = "blake2")]
+mod blake2_var;
+mod check;
+mod checksums;
+#[cfg(feature = "sha2")]
+mod cid;
 mod commands_generated;
+mod dynamic;
+mod encoding;
+mod file;
 mod hasher;
 mod hashers_generated;
+mod manifest;
+mod multi;
+mod multihash;
+mod nix32;
+mod parallel;
+mod password;
+#[cfg(feature = "argon2")]
+mod password_argon2;
+#[cfg(feature = "bcrypt")]
+mod password_bcrypt;
+#[cfg(feature = "crypt")]
+mod password_crypt;
+#[cfg(feature = "scrypt")]
+mod password_scrypt;
+mod pbkdf2;
+mod pbkdf2_generated;
+mod registry;
+mod registry_generated;
+#[cfg(any(feature = "sha1", feature = "sha2"))]
+mod resumable;
+#[cfg(feature = "skein")]
+mod skein_var;
+#[cfg(feature = "sha2")]
+mod sri;
+mod state;
+mod verify;
+mod xof;
 
-use nu_plugin::Plugin;
+use nu_plugin::{EngineInterface, Plugin};
+use nu_protocol::{CustomValue, LabeledError};
 
 pub struct HashesPlugin;
 
@@ -12,6 +53,61 @@ impl Plugin for HashesPlugin {
   }
 
   fn commands(&self) -> Vec<Box<dyn nu_plugin::PluginCommand<Plugin = Self>>> {
-    commands_generated::commands()
+    let mut commands = commands_generated::commands();
+    commands.extend(xof::commands());
+    #[cfg(feature = "blake2")]
+    commands.extend(blake2_var::commands());
+    commands.push(Box::new(dynamic::DynamicHasher));
+    commands.push(Box::new(algorithms::Algorithms));
+    #[cfg(feature = "skein")]
+    commands.push(Box::new(skein_var::SkeinHasher));
+    commands.push(Box::new(multi::MultiHasher));
+    commands.push(Box::new(file::FileHasher));
+    commands.push(Box::new(check::Check));
+    commands.push(Box::new(manifest::Manifest));
+    #[cfg(feature = "sha2")]
+    commands.push(Box::new(sri::Sri));
+    #[cfg(feature = "sha2")]
+    commands.push(Box::new(cid::Cid));
+    commands.push(Box::new(verify::Verify));
+    commands.push(Box::new(state::Init));
+    commands.push(Box::new(state::Update));
+    commands.push(Box::new(state::UpdateFile));
+    commands.push(Box::new(state::Finalize));
+    #[cfg(any(feature = "sha1", feature = "sha2"))]
+    commands.push(Box::new(state::Export));
+    #[cfg(any(feature = "sha1", feature = "sha2"))]
+    commands.push(Box::new(state::Import));
+    commands.push(Box::new(pbkdf2::Pbkdf2));
+    #[cfg(feature = "argon2")]
+    commands.push(Box::new(password_argon2::Argon2Hasher));
+    #[cfg(feature = "argon2")]
+    commands.push(Box::new(password_argon2::Argon2Verify));
+    #[cfg(feature = "bcrypt")]
+    commands.push(Box::new(password_bcrypt::BcryptHasher));
+    #[cfg(feature = "bcrypt")]
+    commands.push(Box::new(password_bcrypt::BcryptVerify));
+    #[cfg(feature = "crypt")]
+    commands.push(Box::new(password_crypt::CryptHasher));
+    #[cfg(feature = "crypt")]
+    commands.push(Box::new(password_crypt::CryptVerify));
+    #[cfg(feature = "scrypt")]
+    commands.push(Box::new(password_scrypt::ScryptHasher));
+    #[cfg(feature = "scrypt")]
+    commands.push(Box::new(password_scrypt::ScryptVerify));
+    commands
+  }
+
+  fn custom_value_dropped(
+    &self,
+    engine: &EngineInterface,
+    custom_value: Box<dyn CustomValue>,
+  ) -> Result<(), LabeledError> {
+    if let Some(state) =
+      custom_value.as_any().downcast_ref::<state::HasherState>()
+    {
+      state::drop_state(engine, state)?;
+    }
+    Ok(())
   }
 }
@@ -0,0 +1,353 @@
+//! Contains a command that writes checksum files, which can be verified with
+//! `hash check` or with coreutils and BSD tools.
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  shell_error::io::IoError,
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  NuGlob,
+  PipelineData,
+  ShellError,
+  Signals,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+
+use crate::{
+  checksums,
+  file::{digest_file, expand_patterns, input_paths, FileTarget},
+  hasher::to_hex,
+  registry,
+  HashesPlugin,
+};
+
+pub struct Manifest;
+
+impl PluginCommand for Manifest {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash manifest"
+  }
+
+  fn signature(&self) -> Signature {
+    Signature::build(self.name())
+      .category(Category::Hash)
+      .input_output_types(vec![
+        (Type::Nothing, Type::String),
+        (Type::String, Type::String),
+        (Type::list(Type::String), Type::String),
+        (Type::table(), Type::String),
+      ])
+      .required_named(
+        "algorithm",
+        SyntaxShape::String,
+        "Name of the hash algorithm, same as the name of its command",
+        Some('a'),
+      )
+      .named(
+        "format",
+        SyntaxShape::String,
+        "Format of the manifest: gnu (default), bsd or json",
+        Some('f'),
+      )
+      .switch("recursive", "Include files in subdirectories", Some('r'))
+      .rest(
+        "paths",
+        SyntaxShape::OneOf(vec![SyntaxShape::GlobPattern, SyntaxShape::String]),
+        "Paths or glob patterns of files and directories to include.",
+      )
+  }
+
+  fn description(&self) -> &str {
+    "Write a checksum file for files and directories."
+  }
+
+  fn extra_description(&self) -> &str {
+    "Files are listed in sorted order, so manifests of the same files are \
+     identical. Files in directories are listed with paths relative to the \
+     directory, other files with paths as they were given, so run \
+     `hash check` from the directory. The gnu format can be \
+     verified with coreutils, like `sha512sum --check`, the bsd format with \
+     BSD tools and `sha512sum --check` too, for algorithms they support. Both \
+     can be verified with `hash check`."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Write a manifest of a release directory",
+        example: "hash manifest --algorithm sha512 --recursive dist | save \
+                  dist/SHA512SUMS",
+        result: None,
+      },
+      Example {
+        description: "Write a manifest in BSD tag format",
+        example: "hash manifest --algorithm sha3-256 --format bsd *.tar.gz",
+        result: None,
+      },
+      Example {
+        description: "Write a manifest of files listed by ls in JSON",
+        example: "ls | where type == file | hash manifest --algorithm \
+                  blake2b-512 --format json",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let algorithm: Spanned<String> =
+      call.get_flag("algorithm")?.ok_or_else(|| {
+        LabeledError::new("Missing algorithm").with_label("", head)
+      })?;
+    let algorithm = registry::find(&algorithm)?;
+    let format = match call.get_flag::<Spanned<String>>("format")? {
+      None => Format::Gnu,
+      Some(format) => match format.item.as_str() {
+        "gnu" => Format::Gnu,
+        "bsd" => Format::Bsd,
+        "json" => Format::Json,
+        _ => {
+          return Err(
+            LabeledError::new(format!(
+              "Unknown manifest format `{}`",
+              format.item
+            ))
+            .with_label("format not found", format.span)
+            .with_help("available formats are: gnu, bsd, json"),
+          )
+        }
+      },
+    };
+    let recursive = call.has_flag("recursive")?;
+
+    let mut patterns: Vec<Spanned<NuGlob>> = call.rest(0)?;
+    patterns.extend(input_paths(input)?);
+    // The current directory is only needed to resolve relative paths
+    let cwd = if patterns
+      .iter()
+      .all(|pattern| Path::new(pattern.item.as_ref()).is_absolute())
+    {
+      PathBuf::new()
+    } else {
+      PathBuf::from(engine.get_current_dir()?)
+    };
+    let signals = engine.signals();
+
+    let mut files = Vec::new();
+    for target in expand_patterns(patterns, &cwd, signals) {
+      collect_files(target?, recursive, signals, &mut files)?;
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    files.dedup_by(|a, b| a.name == b.name);
+
+    let mut entries = Vec::with_capacity(files.len());
+    for file in files {
+      let (_, digest) =
+        digest_file(algorithm, None, &file.path, signals, file.span)?;
+      entries.push((file.name, to_hex(&digest)));
+    }
+
+    let manifest = match format {
+      Format::Gnu => entries
+        .iter()
+        .map(|(path, digest)| checksums::gnu_line(digest, path) + "\n")
+        .collect(),
+      Format::Bsd => entries
+        .iter()
+        .map(|(path, digest)| {
+          checksums::bsd_line(algorithm, digest, path) + "\n"
+        })
+        .collect(),
+      Format::Json => {
+        let files = entries
+          .into_iter()
+          .map(|(path, digest)| {
+            serde_json::json!({ "path": path, "digest": digest })
+          })
+          .collect::<Vec<_>>();
+        let manifest = serde_json::json!({
+          "algorithm": algorithm.name,
+          "files": files,
+        });
+        serde_json::to_string_pretty(&manifest)
+          .expect("JSON values are always serializable")
+      }
+    };
+    Ok(Value::string(manifest, head).into_pipeline_data())
+  }
+}
+
+enum Format {
+  Gnu,
+  Bsd,
+  Json,
+}
+
+/// Adds a file, or files in a directory, to `files`. Files in a directory
+/// are named relative to it, so manifests don't depend on where the directory
+/// is.
+fn collect_files(
+  target: FileTarget,
+  recursive: bool,
+  signals: &Signals,
+  files: &mut Vec<FileTarget>,
+) -> Result<(), ShellError> {
+  signals.check(&target.span)?;
+  let metadata = fs::metadata(&target.path).map_err(|error| {
+    IoError::new(error, target.span, target.path.to_path_buf())
+  })?;
+  if !metadata.is_dir() {
+    files.push(target);
+    return Ok(());
+  }
+  walk_dir(
+    &target.path,
+    Path::new(""),
+    target.span,
+    recursive,
+    signals,
+    files,
+  )
+}
+
+/// Adds files in `dir` to `files`, with names prefixed by `name`. Symbolic
+/// links to directories are not followed.
+fn walk_dir(
+  dir: &Path,
+  name: &Path,
+  span: Span,
+  recursive: bool,
+  signals: &Signals,
+  files: &mut Vec<FileTarget>,
+) -> Result<(), ShellError> {
+  signals.check(&span)?;
+  let io_error =
+    |error, path: &Path| IoError::new(error, span, path.to_path_buf());
+  for entry in fs::read_dir(dir).map_err(|e| io_error(e, dir))? {
+    let entry = entry.map_err(|e| io_error(e, dir))?;
+    let path = entry.path();
+    let file_type = entry.file_type().map_err(|e| io_error(e, &path))?;
+    let is_dir = if file_type.is_symlink() {
+      fs::metadata(&path)
+        .map_err(|e| io_error(e, &path))?
+        .is_dir()
+    } else {
+      file_type.is_dir()
+    };
+    let file = FileTarget {
+      name: name.join(entry.file_name()).to_string_lossy().into_owned(),
+      path,
+      span,
+    };
+    if !is_dir {
+      files.push(file);
+    } else if recursive && !file_type.is_symlink() {
+      walk_dir(
+        &file.path,
+        Path::new(&file.name),
+        span,
+        recursive,
+        signals,
+        files,
+      )?;
+    }
+  }
+  Ok(())
+}
+
+#[cfg(all(test, feature = "sha2"))]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+
+  use super::*;
+
+  #[test]
+  fn test_manifest() -> Result<(), ShellError> {
+    let dir = crate::file::TestDir::new("manifest");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("b.txt"), "abcdefghijklmnopqrstuvwxyz").unwrap();
+    std::fs::write(dir.join("a.txt"), "").unwrap();
+    std::fs::write(dir.join("sub").join("c.txt"), "c").unwrap();
+    let dir = dir.display();
+
+    let mut plugin_test =
+      PluginTest::new("hash manifest", HashesPlugin.into())?;
+    let mut manifest = |args: &str| -> Result<String, ShellError> {
+      plugin_test
+        .eval(&format!("hash manifest --algorithm sha512 {args} `{dir}`"))?
+        .into_value(Span::test_data())?
+        .into_string()
+    };
+
+    assert_eq!(
+      manifest("")?,
+      "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce\
+       47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e  \
+       a.txt\n\
+       4dbff86cc2ca1bae1e16468a05cb9881c97f1753bce3619034898faa1aabe429\
+       955a1bf8ec483d7421fe3c1646613a59ed5441fb0f321389f77f48a879c7b1f1  \
+       b.txt\n"
+    );
+    let bsd = manifest("--format bsd --recursive")?;
+    let lines: Vec<_> = bsd.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("SHA512 (a.txt) = cf83"));
+    let sub = Path::new("sub").join("c.txt");
+    assert!(lines[2].starts_with(&format!("SHA512 ({}) = ", sub.display())));
+
+    let json: serde_json::Value =
+      serde_json::from_str(&manifest("--format json")?).unwrap();
+    assert_eq!(json["algorithm"], "sha512");
+    assert_eq!(json["files"][1]["path"], "b.txt");
+    Ok(())
+  }
+
+  #[test]
+  fn test_check_manifest() -> Result<(), ShellError> {
+    let dir = crate::file::TestDir::new("check_manifest");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("a b.txt"), "a").unwrap();
+    std::fs::write(dir.join("sub").join("c.txt"), "c").unwrap();
+
+    for format in ["gnu", "bsd"] {
+      // Entries are relative to the directory, so check from inside it
+      let mut plugin_test =
+        PluginTest::new("hash manifest", HashesPlugin.into())?;
+      plugin_test.engine_state_mut().add_env_var(
+        "PWD".into(),
+        Value::test_string(dir.display().to_string()),
+      );
+      let result = plugin_test
+        .eval(&format!(
+          "hash manifest --algorithm sha512 --format {format} --recursive . \
+           | hash check --algorithm sha512 --strict"
+        ))?
+        .into_value(Span::test_data())?;
+      assert_eq!(
+        result.get_data_by_key("success"),
+        Some(Value::test_bool(true))
+      );
+    }
+    Ok(())
+  }
+}
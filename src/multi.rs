@@ -0,0 +1,268 @@
+//! Contains a command that hashes its input with several algorithms at once,
+//! reading the input only once.
+
+use std::{io::Write, ops::Not};
+
+use nu_cmd_base::input_handler::CmdArgument;
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  ast::CellPath,
+  record,
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  ShellError,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+
+use crate::{
+  encoding::{self, Encoding},
+  hasher::{map_bytes, DynHasher},
+  parallel,
+  registry::{self, Algorithm},
+  HashesPlugin,
+};
+
+pub struct MultiHasher;
+
+struct Arguments {
+  cell_paths: Option<Vec<CellPath>>,
+  encoding: Encoding,
+  algorithms: Vec<&'static Algorithm>,
+}
+
+impl CmdArgument for Arguments {
+  fn take_cell_paths(&mut self) -> Option<Vec<CellPath>> {
+    self.cell_paths.take()
+  }
+}
+
+/// Feeds written bytes to every hasher.
+pub struct MultiWriter(pub Vec<Box<dyn DynHasher>>);
+
+impl Write for MultiWriter {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    for hasher in &mut self.0 {
+      hasher.update(buf);
+    }
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+impl PluginCommand for MultiHasher {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash multi"
+  }
+
+  fn signature(&self) -> Signature {
+    parallel::signature(encoding::signature(
+      Signature::build(self.name())
+        .category(Category::Hash)
+        .input_output_types(vec![
+          (Type::Binary, Type::record()),
+          (Type::String, Type::record()),
+          (Type::table(), Type::table()),
+          (Type::record(), Type::record()),
+        ])
+        .allow_variants_without_examples(true)
+        .required(
+          "algorithms",
+          SyntaxShape::List(Box::new(SyntaxShape::String)),
+          "Names of hash algorithms to apply.",
+        ),
+    ))
+    .rest(
+      "rest",
+      SyntaxShape::CellPath,
+      "Optionally hash data by cell path.",
+    )
+  }
+
+  fn description(&self) -> &str {
+    "Hash a value using several hash algorithms in a single pass."
+  }
+
+  fn extra_description(&self) -> &str {
+    "Returns a record with a digest for each algorithm. Byte streams are read \
+     only once and every chunk is fed to all of the hashers."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Return sha512 and sha3-256 hashes of a string",
+        example: "'abcdefghijklmnopqrstuvwxyz' | hash multi [sha512 sha3-256]",
+        result: Some(Value::test_record(record! {
+          "sha512" => Value::test_string(
+            "4dbff86cc2ca1bae1e16468a05cb9881c97f1753bce3619034898faa1aabe429\
+             955a1bf8ec483d7421fe3c1646613a59ed5441fb0f321389f77f48a879c7b1f1",
+          ),
+          "sha3-256" => Value::test_string(
+            "7cab2dc765e21b241dbc1c255ce620b29f527c6d5e7f5f843e56288f0d707521",
+          ),
+        })),
+      },
+      Example {
+        description: "Hash a file's contents with several algorithms, reading \
+                      it once",
+        example: "open ./nu_0_24_1_windows.zip | hash multi [sha512 sha3-256 \
+                  blake2b-512 streebog512]",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let names: Vec<Spanned<String>> = call.req(0)?;
+    let mut algorithms: Vec<&'static Algorithm> = Vec::new();
+    for name in &names {
+      let algorithm = registry::find(name)?;
+      if !algorithms.iter().any(|a| a.name == algorithm.name) {
+        algorithms.push(algorithm);
+      }
+    }
+    let encoding = Encoding::from_call(call)?;
+    let cell_paths: Vec<CellPath> = call.rest(1)?;
+    let cell_paths = cell_paths.is_empty().not().then_some(cell_paths);
+
+    if let PipelineData::ByteStream(stream, ..) = input {
+      let mut writer =
+        MultiWriter(algorithms.iter().map(|a| (a.new)()).collect());
+      stream.write_to(&mut writer)?;
+      Ok(
+        digests_record(&algorithms, writer.0, encoding, head)
+          .into_pipeline_data(),
+      )
+    } else {
+      parallel::operate(
+        action,
+        Arguments {
+          cell_paths,
+          encoding,
+          algorithms,
+        },
+        input,
+        head,
+        engine.signals(),
+        parallel::from_call(engine, call)?,
+      )
+      .map_err(Into::into)
+    }
+  }
+}
+
+fn action(input: &Value, args: &Arguments, _span: Span) -> Value {
+  map_bytes(input, |bytes, span| {
+    let mut writer =
+      MultiWriter(args.algorithms.iter().map(|a| (a.new)()).collect());
+    writer
+      .write_all(bytes)
+      .expect("writing to hashers doesn't fail");
+    digests_record(&args.algorithms, writer.0, args.encoding, span)
+  })
+}
+
+fn digests_record(
+  algorithms: &[&Algorithm],
+  hashers: Vec<Box<dyn DynHasher>>,
+  encoding: Encoding,
+  span: Span,
+) -> Value {
+  Value::record(
+    algorithms
+      .iter()
+      .zip(hashers)
+      .map(|(algorithm, hasher)| {
+        let digest = hasher.finalize().into_vec();
+        (algorithm.name.to_owned(), encoding.encode(digest, span))
+      })
+      .collect(),
+    span,
+  )
+}
+
+/// Hashes string, binary or byte stream input with every hasher, reading it
+/// once.
+pub fn digest_input(
+  input: PipelineData,
+  hashers: Vec<Box<dyn DynHasher>>,
+  head: Span,
+) -> Result<Vec<Vec<u8>>, ShellError> {
+  let mut writer = MultiWriter(hashers);
+  match input {
+    PipelineData::ByteStream(stream, ..) => stream.write_to(&mut writer)?,
+    PipelineData::Value(Value::String { val, .. }, ..) => writer
+      .write_all(val.as_bytes())
+      .expect("writing to hashers doesn't fail"),
+    PipelineData::Value(Value::Binary { val, .. }, ..) => writer
+      .write_all(&val)
+      .expect("writing to hashers doesn't fail"),
+    PipelineData::Value(Value::Error { error, .. }, ..) => return Err(*error),
+    other => {
+      return Err(ShellError::OnlySupportsThisInputType {
+        exp_input_type: "string or binary".into(),
+        wrong_type: other.get_type().to_string(),
+        dst_span: head,
+        src_span: other.span().unwrap_or(head),
+      })
+    }
+  }
+  Ok(
+    writer
+      .0
+      .into_iter()
+      .map(|hasher| hasher.finalize().into_vec())
+      .collect(),
+  )
+}
+
+#[cfg(all(test, feature = "sha2", feature = "sha3"))]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::{ByteStream, Signals};
+
+  use super::*;
+
+  #[test]
+  fn test_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash multi", HashesPlugin.into())?
+      .test_examples(&MultiHasher.examples())
+  }
+
+  #[test]
+  fn test_byte_stream() -> Result<(), ShellError> {
+    let stream = ByteStream::read_binary(
+      b"abcdefghijklmnopqrstuvwxyz".to_vec(),
+      Span::test_data(),
+      Signals::empty(),
+    );
+    let actual = PluginTest::new("hash multi", HashesPlugin.into())?
+      .eval_with(
+        "hash multi [sha512 sha3-256]",
+        PipelineData::ByteStream(stream, None),
+      )?
+      .into_value(Span::test_data())?;
+    assert_eq!(actual, MultiHasher.examples()[0].result.clone().unwrap());
+    Ok(())
+  }
+}
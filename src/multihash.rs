@@ -0,0 +1,97 @@
+//! Contains multihash and CID encodings of digests, as specified by
+//! https://multiformats.io. Multicodec codes of algorithms come from the
+//! registry, which is generated by the build script.
+
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{LabeledError, Signature, Spanned, SyntaxShape};
+
+/// Multicodec code of raw binary content.
+#[cfg(feature = "sha2")]
+pub const RAW_CODEC: u64 = 0x55;
+
+/// Adds the `--format` flag to a signature.
+pub fn signature(signature: Signature) -> Signature {
+  signature.named(
+    "format",
+    SyntaxShape::String,
+    "Format of the output: digest (default) or multihash",
+    Some('f'),
+  )
+}
+
+/// Reads the `--format` flag of a call. Returns the multicodec code of the
+/// algorithm if a multihash is requested, or an error if the algorithm has
+/// no code.
+pub fn from_call(
+  call: &EvaluatedCall,
+  algorithm: &str,
+  multicodec: Option<u64>,
+) -> Result<Option<u64>, LabeledError> {
+  let Some(format) = call.get_flag::<Spanned<String>>("format")? else {
+    return Ok(None);
+  };
+  match format.item.as_str() {
+    "digest" => Ok(None),
+    "multihash" => {
+      if let Some(key) = call.get_flag_value("key") {
+        return Err(
+          LabeledError::new("Incompatible flags")
+            .with_label("keyed digests have no multihash code", key.span())
+            .with_help("remove --key or --format"),
+        );
+      }
+      multicodec.map(Some).ok_or_else(|| {
+        LabeledError::new(format!("{algorithm} has no multihash code"))
+          .with_label(
+            "this algorithm isn't in the multicodec table",
+            format.span,
+          )
+      })
+    }
+    _ => Err(
+      LabeledError::new(format!("Unknown format `{}`", format.item))
+        .with_label("format not found", format.span)
+        .with_help("available formats are: digest, multihash"),
+    ),
+  }
+}
+
+/// Prefixes a digest with the multicodec code of its algorithm and its
+/// length.
+pub fn encode(code: u64, digest: Vec<u8>) -> Vec<u8> {
+  let mut multihash = Vec::with_capacity(digest.len() + 4);
+  write_varint(code, &mut multihash);
+  write_varint(digest.len() as u64, &mut multihash);
+  multihash.extend(digest);
+  multihash
+}
+
+/// Returns binary of a version 1 CID of raw content.
+#[cfg(feature = "sha2")]
+pub fn cid_v1(multihash: Vec<u8>) -> Vec<u8> {
+  let mut cid = Vec::with_capacity(multihash.len() + 2);
+  write_varint(1, &mut cid);
+  write_varint(RAW_CODEC, &mut cid);
+  cid.extend(multihash);
+  cid
+}
+
+/// Writes an unsigned LEB128 integer.
+fn write_varint(mut n: u64, output: &mut Vec<u8>) {
+  while n >= 0x80 {
+    output.push(n as u8 | 0x80);
+    n >>= 7;
+  }
+  output.push(n as u8);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_encode() {
+    assert_eq!(encode(0x13, vec![0xab; 2]), [0x13, 0x02, 0xab, 0xab]);
+    assert_eq!(encode(0xb240, vec![0xab]), [0xc0, 0xe4, 0x02, 0x01, 0xab]);
+  }
+}
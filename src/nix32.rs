@@ -0,0 +1,46 @@
+//! Contains the base32 encoding used by Nix. It differs from RFC 4648 base32
+//! in both the alphabet and the bit order, and has no padding.
+//!
+//! This file is also included by the build script, so it only uses `std`.
+
+const ALPHABET: &[u8; 32] = b"0123456789abcdfghijklmnpqrsvwxyz";
+
+/// Encodes bytes with the Nix base32 encoding.
+pub fn encode(bytes: &[u8]) -> String {
+  if bytes.is_empty() {
+    return String::new();
+  }
+  let len = (bytes.len() * 8 - 1) / 5 + 1;
+  (0..len)
+    .rev()
+    .map(|n| {
+      let (i, j) = (n * 5 / 8, n * 5 % 8);
+      let low = u32::from(bytes[i]) >> j;
+      let high = bytes.get(i + 1).map_or(0, |&b| u32::from(b) << (8 - j));
+      ALPHABET[((low | high) & 0x1f) as usize] as char
+    })
+    .collect()
+}
+
+/// Decodes a string encoded with the Nix base32 encoding.
+pub fn decode(encoded: &str) -> Option<Vec<u8>> {
+  let len = encoded.len() * 5 / 8;
+  let mut bytes = vec![0u8; len];
+  for (n, c) in encoded.bytes().rev().enumerate() {
+    let digit = ALPHABET.iter().position(|&a| a == c)? as u32;
+    let (i, j) = (n * 5 / 8, n * 5 % 8);
+    let value = digit << j;
+    match bytes.get_mut(i) {
+      Some(byte) => *byte |= value as u8,
+      // Only zero padding bits can be out of range
+      None if value == 0 => {}
+      None => return None,
+    }
+    match bytes.get_mut(i + 1) {
+      Some(byte) => *byte |= (value >> 8) as u8,
+      None if value >> 8 == 0 => {}
+      None => return None,
+    }
+  }
+  Some(bytes)
+}
@@ -0,0 +1,314 @@
+//! Contains parallel hashing of independent values, like rows of a table or
+//! files, which is enabled with the `--threads` flag or the `threads` field
+//! of the plugin configuration.
+
+use std::{
+  num::NonZeroUsize,
+  ops::Not,
+  sync::{Arc, OnceLock},
+};
+
+use nu_cmd_base::input_handler::{self, CmdArgument};
+use nu_plugin::{EngineInterface, EvaluatedCall};
+use nu_protocol::{
+  ast::CellPath,
+  LabeledError,
+  ListStream,
+  PipelineData,
+  ShellError,
+  Signals,
+  Signature,
+  Span,
+  SyntaxShape,
+  Value,
+};
+use rayon::{
+  iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator},
+  ThreadPool,
+  ThreadPoolBuilder,
+};
+
+/// Number of values each thread gets per batch. Values are read from the
+/// input in batches, so streams aren't collected as a whole.
+const BATCH_SIZE: usize = 1024;
+/// Number of threads per logical CPU, that `--threads` is limited to. More
+/// threads only compete for the same CPUs.
+const THREADS_PER_CPU: usize = 4;
+
+/// Thread pool with the largest number of threads calls may use. Starting
+/// threads costs more than hashing small inputs, so the pool is kept between
+/// calls of the plugin, and each call uses only as many threads as it asks.
+static POOL: OnceLock<ThreadPool> = OnceLock::new();
+
+/// Threads of the pool, that a call uses.
+#[derive(Clone, Copy)]
+pub struct Threads {
+  pool: &'static ThreadPool,
+  count: usize,
+}
+
+/// Adds the `--threads` flag to a signature.
+pub fn signature(signature: Signature) -> Signature {
+  signature.named(
+    "threads",
+    SyntaxShape::Int,
+    "Number of threads to use, `threads` of the plugin config or 1 by default",
+    Some('t'),
+  )
+}
+
+/// Reads the `--threads` flag of a call, or the `threads` field of the plugin
+/// configuration if the flag isn't set. Returns threads of the pool if more
+/// than one thread is requested. The number of threads is limited to
+/// `THREADS_PER_CPU` per logical CPU.
+pub fn from_call(
+  engine: &EngineInterface,
+  call: &EvaluatedCall,
+) -> Result<Option<Threads>, LabeledError> {
+  let threads = match call.get_flag_value("threads") {
+    Some(threads) => threads,
+    None => {
+      let config = engine.get_plugin_config()?;
+      match config
+        .as_ref()
+        .and_then(|config| config.get_data_by_key("threads"))
+      {
+        Some(threads) => threads,
+        None => return Ok(None),
+      }
+    }
+  };
+  let span = threads.span();
+  let threads = threads.as_int()?;
+  if threads < 1 {
+    return Err(
+      LabeledError::new("Invalid number of threads")
+        .with_label("must be positive", span),
+    );
+  }
+  let cpus = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+  let max_threads = cpus * THREADS_PER_CPU;
+  let count = usize::try_from(threads)
+    .unwrap_or(usize::MAX)
+    .min(max_threads);
+  if count == 1 {
+    return Ok(None);
+  }
+  let pool = match POOL.get() {
+    Some(pool) => pool,
+    None => {
+      let pool = ThreadPoolBuilder::new()
+        .num_threads(max_threads)
+        .build()
+        .map_err(|error| {
+          LabeledError::new("Can't start threads")
+            .with_label(error.to_string(), span)
+        })?;
+      // Another call may have started a pool meanwhile, this one is dropped
+      POOL.get_or_init(|| pool)
+    }
+  };
+  Ok(Some(Threads { pool, count }))
+}
+
+/// Maps `items` with `f` on `threads`, preserving their order, and returns
+/// them as a stream. When interrupted, the stream ends with an error, so a
+/// partial table isn't taken for a complete one.
+pub fn stream<T, F>(
+  items: impl Iterator<Item = T> + Send + 'static,
+  threads: Threads,
+  signals: Signals,
+  span: Span,
+  f: F,
+) -> PipelineData
+where
+  T: Send + 'static,
+  F: Fn(T) -> Value + Send + Sync + 'static,
+{
+  let values = map(items, threads, signals, span, f);
+  // Interruptions are handled by `map`
+  ListStream::new(values, span, Signals::empty()).into()
+}
+
+fn map<T, F>(
+  items: impl Iterator<Item = T> + Send + 'static,
+  threads: Threads,
+  signals: Signals,
+  span: Span,
+  f: F,
+) -> impl Iterator<Item = Value> + Send + 'static
+where
+  T: Send + 'static,
+  F: Fn(T) -> Value + Send + Sync + 'static,
+{
+  let batch_size = threads.count * BATCH_SIZE;
+  let mut items = items.fuse();
+  let mut interrupted = false;
+  std::iter::from_fn(move || {
+    if interrupted {
+      return None;
+    }
+    let batch: Vec<T> = items.by_ref().take(batch_size).collect();
+    if batch.is_empty() {
+      return None;
+    }
+    // Splits the batch into at most `count` jobs, so a call doesn't use more
+    // threads of the pool than it asked for
+    let min_len = batch.len().div_ceil(threads.count);
+    let values: Vec<Option<Value>> = threads.pool.install(|| {
+      batch
+        .into_par_iter()
+        .with_min_len(min_len)
+        .map(|item| signals.interrupted().not().then(|| f(item)))
+        .collect()
+    });
+    if let Err(error) = signals.check(&span) {
+      interrupted = true;
+      return Some(vec![Value::error(error, span)]);
+    }
+    Some(values.into_iter().flatten().collect())
+  })
+  .flatten()
+}
+
+/// Same as `operate` of `nu_cmd_base`, but maps values of lists and list
+/// streams on `threads`, if there are any.
+pub fn operate<C, A>(
+  cmd: C,
+  mut arg: A,
+  input: PipelineData,
+  span: Span,
+  signals: &Signals,
+  threads: Option<Threads>,
+) -> Result<PipelineData, ShellError>
+where
+  A: CmdArgument + Send + Sync + 'static,
+  C: Fn(&Value, &A, Span) -> Value + Send + Sync + 'static + Clone + Copy,
+{
+  let Some(threads) = threads else {
+    return input_handler::operate(cmd, arg, input, span, signals);
+  };
+  let values: Box<dyn Iterator<Item = Value> + Send> = match input {
+    PipelineData::Value(Value::List { vals, .. }, ..) => {
+      Box::new(vals.into_iter())
+    }
+    PipelineData::ListStream(stream, ..) => Box::new(stream.into_iter()),
+    input => return input_handler::operate(cmd, arg, input, span, signals),
+  };
+  let cell_paths = arg.take_cell_paths();
+  let arg = Arc::new(arg);
+  Ok(stream(
+    values,
+    threads,
+    signals.clone(),
+    span,
+    move |value| apply(cmd, &arg, cell_paths.as_deref(), value, span),
+  ))
+}
+
+/// Applies `cmd` to a value, or to its cells under `cell_paths`, the same way
+/// `operate` does.
+fn apply<C, A>(
+  cmd: C,
+  arg: &A,
+  cell_paths: Option<&[CellPath]>,
+  mut value: Value,
+  span: Span,
+) -> Value
+where
+  C: Fn(&Value, &A, Span) -> Value,
+{
+  let Some(cell_paths) = cell_paths else {
+    return match value {
+      // Propagate errors inside the input
+      Value::Error { .. } => value,
+      _ => cmd(&value, arg, span),
+    };
+  };
+  for path in cell_paths {
+    let result = value.update_cell_path(
+      &path.members,
+      Box::new(|old| match old {
+        // Propagate errors inside the input
+        Value::Error { .. } => old.clone(),
+        _ => cmd(old, arg, span),
+      }),
+    );
+    if let Err(error) = result {
+      return Value::error(error, span);
+    }
+  }
+  value
+}
+
+#[cfg(all(test, feature = "sha2"))]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use std::sync::atomic::AtomicBool;
+
+  use nu_protocol::{record, IntoPipelineData};
+
+  use super::*;
+  use crate::HashesPlugin;
+
+  #[test]
+  fn test_operate() -> Result<(), ShellError> {
+    let mut plugin_test = PluginTest::new("hash sha512", HashesPlugin.into())?;
+    // More values than a single batch, so batches are joined in order too
+    let count = 3 * BATCH_SIZE + 1;
+    let strings: Vec<Value> = (0..count)
+      .map(|i| Value::test_string(i.to_string()))
+      .collect();
+    let table: Vec<Value> = (0..count)
+      .map(|i| {
+        Value::test_record(record! {
+          "name" => Value::test_string(i.to_string()),
+          "size" => Value::test_int(i as i64),
+        })
+      })
+      .collect();
+    let mut hash = |command: &str, input: &Vec<Value>| {
+      plugin_test
+        .eval_with(
+          command,
+          Value::test_list(input.clone()).into_pipeline_data(),
+        )
+        .and_then(|data| data.into_value(Span::test_data()))
+    };
+    assert_eq!(
+      hash("hash sha512 --threads 2", &strings)?,
+      hash("hash sha512", &strings)?
+    );
+    assert_eq!(
+      hash("hash digest --algorithm sha384 --threads 3 name", &table)?,
+      hash("hash digest --algorithm sha384 name", &table)?
+    );
+    // Excessive numbers of threads are limited, and the pool is reused
+    assert_eq!(
+      hash("hash sha512 --threads 1000000", &strings)?,
+      hash("hash sha512 --threads 1000000", &strings)?
+    );
+    assert!(hash("hash sha512 --threads 0", &strings).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_interrupted() {
+    let pool = ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+    let threads = Threads {
+      pool: Box::leak(Box::new(pool)),
+      count: 2,
+    };
+    let signals = Signals::new(Arc::new(AtomicBool::new(true)));
+    let values: Vec<Value> =
+      map(0..10, threads, signals, Span::test_data(), |i| {
+        Value::test_int(i)
+      })
+      .collect();
+    assert!(matches!(
+      values.as_slice(),
+      [Value::Error { error, .. }]
+        if matches!(**error, ShellError::Interrupted { .. })
+    ));
+  }
+}
@@ -0,0 +1,179 @@
+//! Contains helpers shared by password hashing commands. Unlike digests,
+//! password hashes are salted and returned as strings in PHC format, which
+//! hold the algorithm, its parameters and the salt along with the hash.
+
+#![cfg_attr(
+  not(any(feature = "argon2", feature = "bcrypt", feature = "scrypt")),
+  allow(dead_code)
+)]
+
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{
+  Category,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  ShellError,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+use password_hash::{
+  rand_core::OsRng,
+  PasswordHash,
+  PasswordVerifier,
+  SaltString,
+};
+
+/// Adds the `--salt` flag to a signature.
+pub fn salt_signature(signature: Signature) -> Signature {
+  signature.named(
+    "salt",
+    SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+    "Salt as a string or binary, 16 random bytes by default",
+    Some('s'),
+  )
+}
+
+/// Reads the `--salt` flag of a call, or generates a random salt if it isn't
+/// set. Salts shorter than `min_length` bytes are rejected.
+#[cfg_attr(not(any(feature = "argon2", feature = "scrypt")), allow(dead_code))]
+pub fn salt_from_call(
+  call: &EvaluatedCall,
+  min_length: usize,
+) -> Result<SaltString, LabeledError> {
+  let Some(salt) = call.get_flag::<Spanned<Vec<u8>>>("salt")? else {
+    return Ok(SaltString::generate(&mut OsRng));
+  };
+  if salt.item.len() < min_length {
+    return Err(LabeledError::new("Invalid salt length").with_label(
+      format!(
+        "{} bytes long, but must be at least {min_length} bytes long",
+        salt.item.len()
+      ),
+      salt.span,
+    ));
+  }
+  SaltString::encode_b64(&salt.item).map_err(|error| {
+    LabeledError::new("Invalid salt").with_label(error.to_string(), salt.span)
+  })
+}
+
+/// Returns an error about a parameter of `algorithm`, labeled at `span`.
+#[cfg_attr(not(any(feature = "argon2", feature = "scrypt")), allow(dead_code))]
+pub fn invalid_parameter(
+  algorithm: &str,
+  span: Span,
+  label: &str,
+) -> LabeledError {
+  LabeledError::new(format!("Invalid {algorithm} parameter"))
+    .with_label(label, span)
+}
+
+/// Reads a non-negative integer flag of a call along with its span. If the
+/// flag isn't set, `default` is returned with the span of the call.
+#[cfg_attr(not(any(feature = "argon2", feature = "scrypt")), allow(dead_code))]
+pub fn parameter(
+  call: &EvaluatedCall,
+  algorithm: &str,
+  name: &str,
+  default: u32,
+) -> Result<Spanned<u32>, LabeledError> {
+  let Some(value) = call.get_flag::<Spanned<i64>>(name)? else {
+    return Ok(Spanned {
+      item: default,
+      span: call.head,
+    });
+  };
+  let item = u32::try_from(value.item).map_err(|_| {
+    invalid_parameter(
+      algorithm,
+      value.span,
+      &format!("must be from 0 to {}", u32::MAX),
+    )
+  })?;
+  Ok(Spanned {
+    item,
+    span: value.span,
+  })
+}
+
+/// Reads a password from a string, binary or byte stream input.
+pub fn read_password(
+  input: PipelineData,
+  head: Span,
+) -> Result<Vec<u8>, ShellError> {
+  match input {
+    PipelineData::ByteStream(stream, ..) => stream.into_bytes(),
+    PipelineData::Value(Value::String { val, .. }, ..) => Ok(val.into_bytes()),
+    PipelineData::Value(Value::Binary { val, .. }, ..) => Ok(val),
+    PipelineData::Value(Value::Error { error, .. }, ..) => Err(*error),
+    other => Err(ShellError::OnlySupportsThisInputType {
+      exp_input_type: "string or binary".into(),
+      wrong_type: other.get_type().to_string(),
+      dst_span: head,
+      src_span: other.span().unwrap_or(head),
+    }),
+  }
+}
+
+/// Builds the signature of a command, that checks a password against a hash
+/// described by `hash`.
+pub fn verify_signature(name: &str, hash: &str) -> Signature {
+  Signature::build(name)
+    .category(Category::Hash)
+    .input_output_types(vec![
+      (Type::Binary, Type::Bool),
+      (Type::String, Type::Bool),
+    ])
+    .required(
+      "hash",
+      SyntaxShape::String,
+      format!("{hash} of the password"),
+    )
+    .switch("error", "Fail instead of returning false on mismatch", None)
+}
+
+/// Checks the password of the input against the PHC string in the first
+/// argument of a call with one of `verifiers`.
+#[cfg_attr(not(any(feature = "argon2", feature = "scrypt")), allow(dead_code))]
+pub fn verify(
+  call: &EvaluatedCall,
+  input: PipelineData,
+  verifiers: &[&dyn PasswordVerifier],
+) -> Result<PipelineData, LabeledError> {
+  let head = call.head;
+  let hash: Spanned<String> = call.req(0)?;
+  let invalid_hash = |error: password_hash::Error| {
+    LabeledError::new("Invalid password hash")
+      .with_label(error.to_string(), hash.span)
+  };
+  let parsed = PasswordHash::new(&hash.item).map_err(invalid_hash)?;
+  let password = read_password(input, head)?;
+  // Comparison is done in constant time by the verifiers
+  let matches = match parsed.verify_password(verifiers, password) {
+    Ok(()) => true,
+    Err(password_hash::Error::Password) => false,
+    Err(error) => return Err(invalid_hash(error)),
+  };
+  verify_result(call, matches, hash.span)
+}
+
+/// Returns whether the password matches, or fails on mismatch if `--error`
+/// is set.
+pub fn verify_result(
+  call: &EvaluatedCall,
+  matches: bool,
+  hash_span: Span,
+) -> Result<PipelineData, LabeledError> {
+  if !matches && call.has_flag("error")? {
+    return Err(
+      LabeledError::new("Password mismatch")
+        .with_label("password doesn't match this hash", hash_span),
+    );
+  }
+  Ok(Value::bool(matches, call.head).into_pipeline_data())
+}
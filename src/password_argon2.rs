@@ -0,0 +1,331 @@
+//! Contains commands that hash passwords with Argon2 and check passwords
+//! against Argon2 hashes.
+
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+
+use crate::{password, HashesPlugin};
+
+pub struct Argon2Hasher;
+
+impl PluginCommand for Argon2Hasher {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash argon2"
+  }
+
+  fn signature(&self) -> Signature {
+    password::salt_signature(
+      Signature::build(self.name())
+        .category(Category::Hash)
+        .input_output_types(vec![
+          (Type::Binary, Type::String),
+          (Type::String, Type::String),
+        ])
+        .named(
+          "variant",
+          SyntaxShape::String,
+          "Variant of the algorithm: id, i or d, id by default",
+          None,
+        )
+        .named(
+          "memory",
+          SyntaxShape::Int,
+          format!("Memory size in KiB, {} by default", Params::DEFAULT_M_COST),
+          Some('m'),
+        )
+        .named(
+          "iterations",
+          SyntaxShape::Int,
+          format!(
+            "Number of iterations, {} by default",
+            Params::DEFAULT_T_COST
+          ),
+          Some('i'),
+        )
+        .named(
+          "parallelism",
+          SyntaxShape::Int,
+          format!(
+            "Degree of parallelism, {} by default",
+            Params::DEFAULT_P_COST
+          ),
+          Some('p'),
+        )
+        .named(
+          "length",
+          SyntaxShape::Int,
+          format!(
+            "Output length in bytes, {} by default",
+            Params::DEFAULT_OUTPUT_LEN
+          ),
+          Some('l'),
+        ),
+    )
+  }
+
+  fn description(&self) -> &str {
+    "Hash a password using the argon2 password hashing function."
+  }
+
+  fn extra_description(&self) -> &str {
+    "The hash is returned as a PHC string, that holds the variant, the \
+     parameters and the salt as well. Defaults follow the OWASP \
+     recommendations for Argon2id. Check passwords against the hash with \
+     `hash argon2 verify`."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Hash a password with a random salt",
+        example: "$password | hash argon2",
+        result: None,
+      },
+      Example {
+        description: "Hash a password with explicit parameters (test vector \
+                      of the reference implementation)",
+        example: "'password' | hash argon2 --variant i --salt somesalt \
+                  --memory 65536 --iterations 2 --parallelism 4 --length 24",
+        result: Some(Value::string(
+          "$argon2i$v=19$m=65536,t=2,p=4$c29tZXNhbHQ$\
+           RdescudvJCsgt3ub+b+dWRWJTmaaJObG",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Hash passwords of a table column",
+        example: "$accounts | update password { hash argon2 }",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let algorithm = match call.get_flag::<Spanned<String>>("variant")? {
+      Some(variant) => match variant.item.as_str() {
+        "id" => Algorithm::Argon2id,
+        "i" => Algorithm::Argon2i,
+        "d" => Algorithm::Argon2d,
+        _ => {
+          return Err(
+            LabeledError::new(format!("Unknown variant `{}`", variant.item))
+              .with_label("variant not found", variant.span)
+              .with_help("available variants are: id, i, d"),
+          )
+        }
+      },
+      None => Algorithm::Argon2id,
+    };
+    let memory = parameter(call, "memory", Params::DEFAULT_M_COST)?;
+    let iterations = parameter(call, "iterations", Params::DEFAULT_T_COST)?;
+    let parallelism = parameter(call, "parallelism", Params::DEFAULT_P_COST)?;
+    let length = parameter(call, "length", Params::DEFAULT_OUTPUT_LEN as u32)?;
+    if !(Params::MIN_P_COST..=Params::MAX_P_COST).contains(&parallelism.item) {
+      return Err(invalid_parameter(
+        parallelism.span,
+        &format!(
+          "must be from {} to {}",
+          Params::MIN_P_COST,
+          Params::MAX_P_COST
+        ),
+      ));
+    }
+    // Each lane takes at least 8 blocks of 1 KiB
+    let min_memory = Params::MIN_M_COST.max(parallelism.item * 8);
+    if memory.item < min_memory {
+      return Err(invalid_parameter(
+        memory.span,
+        &format!(
+          "must be at least {min_memory} KiB with parallelism of {}",
+          parallelism.item
+        ),
+      ));
+    }
+    if iterations.item < Params::MIN_T_COST {
+      return Err(invalid_parameter(iterations.span, "must be positive"));
+    }
+    if !(10..=64).contains(&length.item) {
+      return Err(invalid_parameter(
+        length.span,
+        "PHC strings only hold hashes from 10 to 64 bytes long",
+      ));
+    }
+    // Other limits are checked above, only the memory cost may be too large
+    let params = Params::new(
+      memory.item,
+      iterations.item,
+      parallelism.item,
+      Some(length.item as usize),
+    )
+    .map_err(|error| invalid_parameter(memory.span, &error.to_string()))?;
+    let salt = password::salt_from_call(call, argon2::MIN_SALT_LEN)?;
+    let password = password::read_password(input, head)?;
+
+    let hash = Argon2::new(algorithm, Version::V0x13, params)
+      .hash_password(&password, &salt)
+      .map_err(|error| {
+        LabeledError::new("Can't hash password")
+          .with_label(error.to_string(), head)
+      })?;
+    Ok(Value::string(hash.to_string(), head).into_pipeline_data())
+  }
+}
+
+fn invalid_parameter(span: Span, label: &str) -> LabeledError {
+  password::invalid_parameter("argon2", span, label)
+}
+
+fn parameter(
+  call: &EvaluatedCall,
+  name: &str,
+  default: u32,
+) -> Result<Spanned<u32>, LabeledError> {
+  password::parameter(call, "argon2", name, default)
+}
+
+pub struct Argon2Verify;
+
+impl PluginCommand for Argon2Verify {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash argon2 verify"
+  }
+
+  fn signature(&self) -> Signature {
+    password::verify_signature(self.name(), "argon2 hash in PHC string format")
+  }
+
+  fn description(&self) -> &str {
+    "Check a password against an argon2 hash."
+  }
+
+  fn extra_description(&self) -> &str {
+    "The variant, the parameters and the salt are read from the hash."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Check a password",
+        example: "'password' | hash argon2 verify \
+                  '$argon2i$v=19$m=65536,t=2,p=4$c29tZXNhbHQ$\
+                  RdescudvJCsgt3ub+b+dWRWJTmaaJObG'",
+        result: Some(Value::test_bool(true)),
+      },
+      Example {
+        description: "Fail if a password doesn't match the stored hash",
+        example: "$password | hash argon2 verify $account.password --error",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    password::verify(call, input, &[&Argon2::default()])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::ShellError;
+
+  use super::*;
+
+  #[test]
+  fn test_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash argon2", HashesPlugin.into())?
+      .test_examples(&Argon2Hasher.examples())?;
+    PluginTest::new("hash argon2 verify", HashesPlugin.into())?
+      .test_examples(&Argon2Verify.examples())
+  }
+
+  #[test]
+  fn test_hash_and_verify() -> Result<(), ShellError> {
+    let mut plugin_test = PluginTest::new("hash argon2", HashesPlugin.into())?;
+    let mut eval = |command: &str| {
+      plugin_test
+        .eval(command)
+        .and_then(|data| data.into_value(Span::test_data()))
+    };
+    let args = "--memory 64 --iterations 1";
+    let hash =
+      eval(&format!("'secret' | hash argon2 {args}"))?.into_string()?;
+    assert!(hash.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+    // Salts are random
+    assert_ne!(
+      eval(&format!("'secret' | hash argon2 {args}"))?.into_string()?,
+      hash
+    );
+    assert_eq!(
+      eval(&format!("'secret' | hash argon2 verify '{hash}'"))?,
+      Value::test_bool(true)
+    );
+    assert_eq!(
+      eval(&format!("'wrong' | hash argon2 verify '{hash}'"))?,
+      Value::test_bool(false)
+    );
+    assert!(
+      eval(&format!("'wrong' | hash argon2 verify '{hash}' --error")).is_err()
+    );
+    assert!(eval("'secret' | hash argon2 verify 'not a hash'").is_err());
+    assert!(
+      eval(&format!("'secret' | hash argon2 {args} --salt short")).is_err()
+    );
+    assert!(
+      eval(&format!("'secret' | hash argon2 {args} --variant x")).is_err()
+    );
+    assert!(eval("'secret' | hash argon2 --memory 1").is_err());
+    for flags in [
+      "--parallelism 9 --memory 64",
+      "--iterations 0",
+      "--parallelism 0",
+      "--parallelism 16777216",
+      "--length 9",
+      "--length 65",
+      "--memory -1",
+    ] {
+      let error = plugin_test
+        .eval(&format!("'secret' | hash argon2 {flags}"))
+        .unwrap_err();
+      let ShellError::LabeledError(error) = error else {
+        panic!("{flags}: {error:?}");
+      };
+      assert_eq!(error.msg, "Invalid argon2 parameter", "{flags}");
+      // Errors point at the last flag, not at the whole call
+      let flag = &flags[flags.rfind("--").unwrap()..];
+      let span = error.labels[0].span;
+      assert_eq!(span.end - span.start, flag.len(), "{flags}");
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,301 @@
+//! Contains commands that hash passwords with bcrypt and check passwords
+//! against bcrypt hashes in modular crypt format.
+
+use bcrypt::{Version, DEFAULT_COST};
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+
+use crate::{password, HashesPlugin};
+
+/// Number of password bytes bcrypt takes into account.
+const MAX_PASSWORD_LENGTH: usize = 72;
+const MIN_COST: u32 = 4;
+const MAX_COST: u32 = 31;
+const SALT_LENGTH: usize = 16;
+
+/// Adds the `--truncate` flag to a signature.
+fn truncate_signature(signature: Signature) -> Signature {
+  signature.switch(
+    "truncate",
+    format!(
+      "Only use the first {MAX_PASSWORD_LENGTH} bytes of longer passwords, \
+       instead of failing"
+    ),
+    None,
+  )
+}
+
+/// Reads a password and checks that it fits into bcrypt, unless it may be
+/// truncated.
+fn read_password(
+  call: &EvaluatedCall,
+  input: PipelineData,
+) -> Result<Vec<u8>, LabeledError> {
+  let head = call.head;
+  let span = input.span().unwrap_or(head);
+  let password = password::read_password(input, head)?;
+  if password.len() > MAX_PASSWORD_LENGTH && !call.has_flag("truncate")? {
+    return Err(
+      LabeledError::new("Password is too long")
+        .with_label(
+          format!(
+            "{} bytes long, but bcrypt only uses the first \
+             {MAX_PASSWORD_LENGTH} bytes",
+            password.len()
+          ),
+          span,
+        )
+        .with_help("use --truncate to hash the first 72 bytes only"),
+    );
+  }
+  Ok(password)
+}
+
+pub struct BcryptHasher;
+
+impl PluginCommand for BcryptHasher {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash bcrypt"
+  }
+
+  fn signature(&self) -> Signature {
+    truncate_signature(password::salt_signature(
+      Signature::build(self.name())
+        .category(Category::Hash)
+        .input_output_types(vec![
+          (Type::Binary, Type::String),
+          (Type::String, Type::String),
+        ])
+        .named(
+          "cost",
+          SyntaxShape::Int,
+          format!(
+            "Binary logarithm of the number of rounds, from {MIN_COST} to \
+             {MAX_COST}, {DEFAULT_COST} by default"
+          ),
+          Some('c'),
+        ),
+    ))
+  }
+
+  fn description(&self) -> &str {
+    "Hash a password using the bcrypt password hashing function."
+  }
+
+  fn extra_description(&self) -> &str {
+    "The hash is returned as a `$2b$` string, that holds the cost and the salt \
+     as well. Check passwords against the hash with `hash bcrypt verify`.
+
+bcrypt ignores everything past the first 72 bytes of a password, so longer \
+     passwords are rejected unless `--truncate` is set."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Hash a password with a random salt",
+        example: "$password | hash bcrypt --cost 12",
+        result: None,
+      },
+      Example {
+        description: "Hash a password with an explicit salt (OpenBSD test \
+                      vector)",
+        example: "'U*U' | hash bcrypt --cost 5 --salt \
+                  0x[10410410410410410410410410410410]",
+        result: Some(Value::string(
+          "$2b$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW",
+          Span::test_data(),
+        )),
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let cost = match call.get_flag::<Spanned<i64>>("cost")? {
+      Some(cost) => u32::try_from(cost.item)
+        .ok()
+        .filter(|cost| (MIN_COST..=MAX_COST).contains(cost))
+        .ok_or_else(|| {
+          LabeledError::new("Invalid cost").with_label(
+            format!("cost must be from {MIN_COST} to {MAX_COST}"),
+            cost.span,
+          )
+        })?,
+      None => DEFAULT_COST,
+    };
+    let salt = match call.get_flag::<Spanned<Vec<u8>>>("salt")? {
+      Some(salt) => Some(<[u8; SALT_LENGTH]>::try_from(salt.item).map_err(
+        |item: Vec<u8>| {
+          LabeledError::new("Invalid salt length").with_label(
+            format!(
+              "{} bytes long, but must be {SALT_LENGTH} bytes long",
+              item.len()
+            ),
+            salt.span,
+          )
+        },
+      )?),
+      None => None,
+    };
+    let password = read_password(call, input)?;
+
+    // Passwords that are too long were rejected, so they may be truncated
+    let hash = match salt {
+      Some(salt) => bcrypt::hash_with_salt(&password, cost, salt),
+      None => bcrypt::hash_with_result(&password, cost),
+    }
+    .map_err(|error| {
+      LabeledError::new("Can't hash password")
+        .with_label(error.to_string(), head)
+    })?;
+    Ok(
+      Value::string(hash.format_for_version(Version::TwoB), head)
+        .into_pipeline_data(),
+    )
+  }
+}
+
+pub struct BcryptVerify;
+
+impl PluginCommand for BcryptVerify {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash bcrypt verify"
+  }
+
+  fn signature(&self) -> Signature {
+    truncate_signature(password::verify_signature(
+      self.name(),
+      "bcrypt hash in $2a$, $2b$ or $2y$ format",
+    ))
+  }
+
+  fn description(&self) -> &str {
+    "Check a password against a bcrypt hash."
+  }
+
+  fn extra_description(&self) -> &str {
+    "The cost and the salt are read from the hash. Passwords longer than 72 \
+     bytes are rejected unless `--truncate` is set, as only their first 72 \
+     bytes could have been hashed."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Check a password (OpenBSD test vector)",
+        example: "'U*U' | hash bcrypt verify '$2a$05$\
+                  CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW'",
+        result: Some(Value::test_bool(true)),
+      },
+      Example {
+        description: "Find accounts, that use a weak password",
+        example: "$users | where { 'password' | hash bcrypt verify \
+                  $in.password_hash }",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let hash: Spanned<String> = call.req(0)?;
+    let password = read_password(call, input)?;
+    // The comparison is done in constant time
+    let matches = bcrypt::verify(&password, &hash.item).map_err(|error| {
+      LabeledError::new("Invalid password hash")
+        .with_label(error.to_string(), hash.span)
+    })?;
+    password::verify_result(call, matches, hash.span)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::ShellError;
+
+  use super::*;
+
+  #[test]
+  fn test_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash bcrypt", HashesPlugin.into())?
+      .test_examples(&BcryptHasher.examples())?;
+    PluginTest::new("hash bcrypt verify", HashesPlugin.into())?
+      .test_examples(&BcryptVerify.examples())
+  }
+
+  #[test]
+  fn test_hash_and_verify() -> Result<(), ShellError> {
+    let mut plugin_test = PluginTest::new("hash bcrypt", HashesPlugin.into())?;
+    let mut eval = |command: &str| {
+      plugin_test
+        .eval(command)
+        .and_then(|data| data.into_value(Span::test_data()))
+    };
+    let hash = eval("'secret' | hash bcrypt --cost 4")?.into_string()?;
+    assert!(hash.starts_with("$2b$04$"));
+    assert_eq!(
+      eval(&format!("'secret' | hash bcrypt verify '{hash}'"))?,
+      Value::test_bool(true)
+    );
+    assert_eq!(
+      eval(&format!("'wrong' | hash bcrypt verify '{hash}'"))?,
+      Value::test_bool(false)
+    );
+    assert!(
+      eval(&format!("'wrong' | hash bcrypt verify '{hash}' --error")).is_err()
+    );
+    // $2y$ hashes of PHP are the same as $2b$ ones
+    let hash = hash.replacen("$2b$", "$2y$", 1);
+    assert_eq!(
+      eval(&format!("'secret' | hash bcrypt verify '{hash}'"))?,
+      Value::test_bool(true)
+    );
+    assert!(eval("'secret' | hash bcrypt verify '$2b$04$short'").is_err());
+    assert!(eval("'secret' | hash bcrypt --cost 3").is_err());
+    assert!(eval("'secret' | hash bcrypt --salt 0x[00]").is_err());
+
+    // Passwords over 72 bytes only differ past the limit
+    let long = "x".repeat(MAX_PASSWORD_LENGTH);
+    assert!(eval(&format!("'{long}' | hash bcrypt --cost 4")).is_ok());
+    assert!(eval(&format!("'{long}y' | hash bcrypt --cost 4")).is_err());
+    let hash = eval(&format!("'{long}y' | hash bcrypt --cost 4 --truncate"))?
+      .into_string()?;
+    assert!(eval(&format!("'{long}z' | hash bcrypt verify '{hash}'")).is_err());
+    assert_eq!(
+      eval(&format!(
+        "'{long}z' | hash bcrypt verify '{hash}' --truncate"
+      ))?,
+      Value::test_bool(true)
+    );
+    Ok(())
+  }
+}
@@ -0,0 +1,357 @@
+//! Contains commands that hash passwords or derive keys with scrypt and check
+//! passwords against scrypt hashes.
+
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+use scrypt::{password_hash::PasswordHasher, Params, Scrypt};
+
+use crate::{password, xof::MAX_LENGTH, HashesPlugin};
+
+/// Largest memory in bytes, that scrypt may use. It's allocated at once, so
+/// larger costs would abort the plugin instead of failing.
+const MAX_MEMORY: u128 = 4 * 1024 * 1024 * 1024;
+
+pub struct ScryptHasher;
+
+impl PluginCommand for ScryptHasher {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash scrypt"
+  }
+
+  fn signature(&self) -> Signature {
+    password::salt_signature(
+      Signature::build(self.name())
+        .category(Category::Hash)
+        .input_output_types(vec![
+          (Type::Binary, Type::Any),
+          (Type::String, Type::Any),
+        ])
+        .named(
+          "log-n",
+          SyntaxShape::Int,
+          format!(
+            "Binary logarithm of the CPU/memory cost N, {} by default",
+            Params::RECOMMENDED_LOG_N
+          ),
+          None,
+        )
+        .named(
+          "block-size",
+          SyntaxShape::Int,
+          format!("Block size r, {} by default", Params::RECOMMENDED_R),
+          Some('r'),
+        )
+        .named(
+          "parallelism",
+          SyntaxShape::Int,
+          format!("Parallelism p, {} by default", Params::RECOMMENDED_P),
+          Some('p'),
+        )
+        .named(
+          "length",
+          SyntaxShape::Int,
+          format!(
+            "Output length in bytes, {} by default, up to 64 or up to \
+             {MAX_LENGTH} with --raw",
+            Params::RECOMMENDED_LEN
+          ),
+          Some('l'),
+        )
+        .switch(
+          "raw",
+          "Return the derived key as binary instead of a PHC string",
+          None,
+        ),
+    )
+  }
+
+  fn description(&self) -> &str {
+    "Hash a password or derive a key using the scrypt key derivation function."
+  }
+
+  fn extra_description(&self) -> &str {
+    "Hashes are returned as PHC strings, that hold the parameters and the salt \
+     as well. Check passwords against them with `hash scrypt verify`. Derived \
+     keys can only be reproduced with the same salt, so `--raw` requires \
+     `--salt`. scrypt takes 128 * r * N bytes of memory, which is limited to \
+     4 GiB."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Hash a password with a random salt",
+        example: "$password | hash scrypt",
+        result: None,
+      },
+      Example {
+        description: "Hash a password with explicit parameters",
+        example: "'password' | hash scrypt --salt NaCl --log-n 10 -r 8 -p 16",
+        result: Some(Value::string(
+          "$scrypt$ln=10,r=8,p=16$TmFDbA$\
+           /bq+HJ00cgB4VucZDQHp/nxq18vII3gw53N2Y0s3MWI",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Derive a key (RFC 7914, section 12)",
+        example: "'password' | hash scrypt --raw --salt NaCl --log-n 10 -r 8 \
+                  -p 16 --length 64",
+        result: Some(Value::binary(
+          vec![
+            0xfd, 0xba, 0xbe, 0x1c, 0x9d, 0x34, 0x72, 0x00, 0x78, 0x56, 0xe7,
+            0x19, 0x0d, 0x01, 0xe9, 0xfe, 0x7c, 0x6a, 0xd7, 0xcb, 0xc8, 0x23,
+            0x78, 0x30, 0xe7, 0x73, 0x76, 0x63, 0x4b, 0x37, 0x31, 0x62, 0x2e,
+            0xaf, 0x30, 0xd9, 0x2e, 0x22, 0xa3, 0x88, 0x6f, 0xf1, 0x09, 0x27,
+            0x9d, 0x98, 0x30, 0xda, 0xc7, 0x27, 0xaf, 0xb9, 0x4a, 0x83, 0xee,
+            0x6d, 0x83, 0x60, 0xcb, 0xdf, 0xa2, 0xcc, 0x06, 0x40,
+          ],
+          Span::test_data(),
+        )),
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let raw = call.has_flag("raw")?;
+    let log_n = parameter(call, "log-n", Params::RECOMMENDED_LOG_N.into())?;
+    let r = parameter(call, "block-size", Params::RECOMMENDED_R)?;
+    let p = parameter(call, "parallelism", Params::RECOMMENDED_P)?;
+    let length = parameter(call, "length", Params::RECOMMENDED_LEN as u32)?;
+    // Limits of two parameters are labeled at the one, that was set
+    let either =
+      |span: Span, other: Span| if span == head { other } else { span };
+    if r.item == 0 {
+      return Err(invalid_parameter(r.span, "must be positive"));
+    }
+    if p.item == 0 {
+      return Err(invalid_parameter(p.span, "must be positive"));
+    }
+    if u64::from(r.item) * u64::from(p.item) >= 1 << 30 {
+      return Err(invalid_parameter(
+        either(p.span, r.span),
+        "block size times parallelism must be less than 2^30",
+      ));
+    }
+    if log_n.item == 0 || log_n.item >= 64 {
+      return Err(invalid_parameter(log_n.span, "must be from 1 to 63"));
+    }
+    if log_n.item >= r.item.saturating_mul(16) {
+      return Err(invalid_parameter(
+        either(log_n.span, r.span),
+        "must be less than 16 times the block size",
+      ));
+    }
+    // scrypt allocates N and p blocks of 128 * r bytes at once
+    let block_size = 128 * u128::from(r.item);
+    if (1 << log_n.item) * block_size > MAX_MEMORY {
+      return Err(invalid_parameter(
+        either(log_n.span, r.span),
+        &format!("N blocks of 128 * r bytes must fit in {MAX_MEMORY} bytes"),
+      ));
+    }
+    if u128::from(p.item) * block_size > MAX_MEMORY {
+      return Err(invalid_parameter(
+        either(p.span, r.span),
+        &format!("p blocks of 128 * r bytes must fit in {MAX_MEMORY} bytes"),
+      ));
+    }
+    let max_length = if raw { MAX_LENGTH as u32 } else { 64 };
+    let min_length = if raw { 1 } else { 10 };
+    if !(min_length..=max_length).contains(&length.item) {
+      return Err(invalid_parameter(
+        length.span,
+        &if raw {
+          format!("must be from 1 to {MAX_LENGTH}")
+        } else {
+          "PHC strings only hold hashes from 10 to 64 bytes long".to_owned()
+        },
+      ));
+    }
+    // Raw keys can be of any length, which the parameters don't take
+    let params_length = if raw {
+      Params::RECOMMENDED_LEN
+    } else {
+      length.item as usize
+    };
+    // Other limits are checked above, only N may not fit in `usize`
+    let params =
+      Params::new(log_n.item as u8, r.item, p.item, params_length)
+        .map_err(|error| invalid_parameter(log_n.span, &error.to_string()))?;
+
+    let value = if raw {
+      let salt: Vec<u8> = call.get_flag("salt")?.ok_or_else(|| {
+        LabeledError::new("Missing salt")
+          .with_label("derived keys can't be reproduced without a salt", head)
+          .with_help("add --salt")
+      })?;
+      let password = password::read_password(input, head)?;
+      let mut key = vec![0; length.item as usize];
+      scrypt::scrypt(&password, &salt, &params, &mut key)
+        .map_err(|error| invalid_parameter(length.span, &error.to_string()))?;
+      Value::binary(key, head)
+    } else {
+      // scrypt takes salts of any length
+      let salt = password::salt_from_call(call, 0)?;
+      let password = password::read_password(input, head)?;
+      let hash = Scrypt
+        .hash_password_customized(&password, None, None, params, &salt)
+        .map_err(|error| {
+          LabeledError::new("Can't hash password")
+            .with_label(error.to_string(), head)
+        })?;
+      Value::string(hash.to_string(), head)
+    };
+    Ok(value.into_pipeline_data())
+  }
+}
+
+fn invalid_parameter(span: Span, label: &str) -> LabeledError {
+  password::invalid_parameter("scrypt", span, label)
+}
+
+fn parameter(
+  call: &EvaluatedCall,
+  name: &str,
+  default: u32,
+) -> Result<Spanned<u32>, LabeledError> {
+  password::parameter(call, "scrypt", name, default)
+}
+
+pub struct ScryptVerify;
+
+impl PluginCommand for ScryptVerify {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash scrypt verify"
+  }
+
+  fn signature(&self) -> Signature {
+    password::verify_signature(self.name(), "scrypt hash in PHC string format")
+  }
+
+  fn description(&self) -> &str {
+    "Check a password against an scrypt hash."
+  }
+
+  fn extra_description(&self) -> &str {
+    "The parameters and the salt are read from the hash."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![Example {
+      description: "Check a password",
+      example: "'password' | hash scrypt verify \
+                '$scrypt$ln=10,r=8,p=16$TmFDbA$\
+                /bq+HJ00cgB4VucZDQHp/nxq18vII3gw53N2Y0s3MWI'",
+      result: Some(Value::test_bool(true)),
+    }]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    password::verify(call, input, &[&Scrypt])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::ShellError;
+
+  use super::*;
+
+  #[test]
+  fn test_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash scrypt", HashesPlugin.into())?
+      .test_examples(&ScryptHasher.examples())?;
+    PluginTest::new("hash scrypt verify", HashesPlugin.into())?
+      .test_examples(&ScryptVerify.examples())
+  }
+
+  #[test]
+  fn test_parameters() -> Result<(), ShellError> {
+    let mut plugin_test = PluginTest::new("hash scrypt", HashesPlugin.into())?;
+    let mut eval = |command: &str| {
+      plugin_test
+        .eval(command)
+        .and_then(|data| data.into_value(Span::test_data()))
+    };
+    // RFC 7914, section 12
+    assert_eq!(
+      eval("'' | hash scrypt --raw --salt '' --log-n 4 -r 1 -p 1 -l 64")?,
+      Value::test_binary(vec![
+        0x77, 0xd6, 0x57, 0x62, 0x38, 0x65, 0x7b, 0x20, 0x3b, 0x19, 0xca, 0x42,
+        0xc1, 0x8a, 0x04, 0x97, 0xf1, 0x6b, 0x48, 0x44, 0xe3, 0x07, 0x4a, 0xe8,
+        0xdf, 0xdf, 0xfa, 0x3f, 0xed, 0xe2, 0x14, 0x42, 0xfc, 0xd0, 0x06, 0x9d,
+        0xed, 0x09, 0x48, 0xf8, 0x32, 0x6a, 0x75, 0x3a, 0x0f, 0xc8, 0x1f, 0x17,
+        0xe8, 0xd3, 0xe0, 0xfb, 0x2e, 0x0d, 0x36, 0x28, 0xcf, 0x35, 0xe2, 0x0c,
+        0x38, 0xd1, 0x89, 0x06,
+      ])
+    );
+    let hash = eval("'secret' | hash scrypt --log-n 4")?.into_string()?;
+    assert!(hash.starts_with("$scrypt$ln=4,r=8,p=1$"));
+    assert_eq!(
+      eval(&format!("'secret' | hash scrypt verify '{hash}'"))?,
+      Value::test_bool(true)
+    );
+    assert_eq!(
+      eval(&format!("'wrong' | hash scrypt verify '{hash}'"))?,
+      Value::test_bool(false)
+    );
+    assert!(eval("'secret' | hash scrypt --raw").is_err());
+    // Errors point at the flag, that is out of bounds, not at the whole call
+    let length = format!("--length {}", MAX_LENGTH + 1);
+    for (flags, flag) in [
+      ("--log-n 0", "--log-n 0"),
+      ("-r 1 --log-n 16", "--log-n 16"),
+      ("--log-n 20 -r 1", "--log-n 20"),
+      ("-r 0", "-r 0"),
+      ("-p -1", "-p -1"),
+      ("-r 65536 -p 16384", "-p 16384"),
+      ("--length 9", "--length 9"),
+      ("-r 8 --log-n 40", "--log-n 40"),
+      ("-r 33554432", "-r 33554432"),
+      ("-r 8 -p 4194305", "-p 4194305"),
+      (&format!("--raw --salt NaCl {length}"), &length),
+    ] {
+      let error = plugin_test
+        .eval(&format!("'secret' | hash scrypt {flags}"))
+        .unwrap_err();
+      let ShellError::LabeledError(error) = error else {
+        panic!("{flags}: {error:?}");
+      };
+      assert_eq!(error.msg, "Invalid scrypt parameter", "{flags}");
+      let span = error.labels[0].span;
+      assert_eq!(span.end - span.start, flag.len(), "{flags}");
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,285 @@
+//! Contains a command that derives keys from passwords with PBKDF2, as
+//! specified in RFC 8018. The pseudorandom function is HMAC over any algorithm
+//! of the registry.
+
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  Category,
+  Example,
+  FromValue,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  ShellError,
+  Signals,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+};
+
+use crate::{
+  encoding::{self, Encoding},
+  hasher::DynHasher,
+  password,
+  pbkdf2_generated,
+  registry,
+  xof::MAX_LENGTH,
+  HashesPlugin,
+};
+
+/// Number of iterations between checks for interruption
+const CHECK_INTERVAL: u32 = 1024;
+
+pub struct Pbkdf2;
+
+impl PluginCommand for Pbkdf2 {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash pbkdf2"
+  }
+
+  fn signature(&self) -> Signature {
+    encoding::signature(
+      Signature::build(self.name())
+        .category(Category::Hash)
+        .input_output_types(vec![
+          (Type::Binary, Type::Any),
+          (Type::String, Type::Any),
+        ])
+        .required_named(
+          "algorithm",
+          SyntaxShape::String,
+          "Name of the hash algorithm of HMAC, same as the name of its command",
+          Some('a'),
+        )
+        .required_named(
+          "iterations",
+          SyntaxShape::Int,
+          "Number of iterations",
+          Some('i'),
+        )
+        .required_named(
+          "salt",
+          SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+          "Salt as a string or binary",
+          Some('s'),
+        )
+        .named(
+          "length",
+          SyntaxShape::Int,
+          format!(
+            "Length of the derived key in bytes, up to {MAX_LENGTH}, the \
+             output size of the algorithm by default"
+          ),
+          Some('l'),
+        ),
+    )
+  }
+
+  fn description(&self) -> &str {
+    "Derive a key from a password using PBKDF2 with HMAC over a hash \
+     algorithm."
+  }
+
+  fn extra_description(&self) -> &str {
+    "Any algorithm, that supports HMAC, can be used, as well as sha256. \
+     Keys are hex-encoded by default, or returned as binary with `--binary`."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    let mut examples = pbkdf2_generated::examples();
+    examples.push(Example {
+      description: "Derive a 256-bit encryption key with HMAC-SHA512",
+      example: "$passphrase | hash pbkdf2 --algorithm sha512 --iterations \
+                210000 --salt $salt --length 32 --binary",
+      result: None,
+    });
+    examples
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let algorithm: Spanned<String> = required(call, "algorithm")?;
+    let iterations: Spanned<i64> = required(call, "iterations")?;
+    let salt: Vec<u8> = required(call, "salt")?;
+    let length: Option<Spanned<i64>> = call.get_flag("length")?;
+    let encoding = Encoding::from_call(call)?;
+
+    let iterations = u32::try_from(iterations.item)
+      .ok()
+      .filter(|&iterations| iterations > 0)
+      .ok_or_else(|| {
+        LabeledError::new("Invalid number of iterations").with_label(
+          format!("must be from 1 to {}", u32::MAX),
+          iterations.span,
+        )
+      })?;
+    let password = password::read_password(input, head)?;
+    let prf = new_hmac(&algorithm, &password)?;
+    let output_size = prf.output_size();
+    let length = match length {
+      Some(length) => {
+        // RFC 8018 limits keys to 2^32 - 1 blocks, but keys are kept in
+        // memory, so they're capped like XOF outputs. The cap doesn't bound
+        // the runtime, which grows with blocks times iterations, so the loop
+        // checks for interruption instead
+        let max_length =
+          MAX_LENGTH.min(output_size.saturating_mul(u32::MAX as usize));
+        usize::try_from(length.item)
+          .ok()
+          .filter(|length| (1..=max_length).contains(length))
+          .ok_or_else(|| {
+            LabeledError::new("Invalid key length").with_label(
+              format!("must be from 1 to {max_length}"),
+              length.span,
+            )
+          })?
+      }
+      None => output_size,
+    };
+
+    let mut key = vec![0; length];
+    pbkdf2(
+      prf.as_ref(),
+      &salt,
+      iterations,
+      &mut key,
+      engine.signals(),
+      head,
+    )?;
+    Ok(encoding.encode(key, head).into_pipeline_data())
+  }
+}
+
+/// Reads a flag, that the signature marks as required. Nushell checks such
+/// flags before running the command, this only guards against other callers.
+fn required<T: FromValue>(
+  call: &EvaluatedCall,
+  name: &str,
+) -> Result<T, LabeledError> {
+  call.get_flag(name)?.ok_or_else(|| {
+    LabeledError::new(format!("Missing required flag --{name}"))
+      .with_label(format!("requires --{name}"), call.head)
+  })
+}
+
+/// Returns HMAC over an algorithm keyed with `key`. SHA-256 has no command of
+/// its own, as Nushell has one, so it's not in the registry.
+fn new_hmac(
+  name: &Spanned<String>,
+  key: &[u8],
+) -> Result<Box<dyn DynHasher>, LabeledError> {
+  #[cfg(feature = "sha2")]
+  if name.item == "sha256" {
+    use hmac::{Mac, SimpleHmac};
+
+    return Ok(Box::new(
+      SimpleHmac::<sha2::Sha256>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length"),
+    ));
+  }
+  let algorithm = registry::find(name)?;
+  (algorithm.hmac)(key).ok_or_else(|| {
+    LabeledError::new(format!("HMAC is not supported by {}", algorithm.name))
+      .with_label("this algorithm can't be keyed", name.span)
+  })
+}
+
+/// Fills `key` with a key derived with `prf`, which is HMAC keyed with the
+/// password. Signals are checked for every block and every
+/// [`CHECK_INTERVAL`] iterations.
+fn pbkdf2(
+  prf: &dyn DynHasher,
+  salt: &[u8],
+  iterations: u32,
+  key: &mut [u8],
+  signals: &Signals,
+  span: Span,
+) -> Result<(), ShellError> {
+  for (index, block) in key.chunks_mut(prf.output_size()).enumerate() {
+    signals.check(&span)?;
+    // Blocks are numbered from 1
+    let mut mac = prf.fork();
+    mac.update(salt);
+    mac.update(&(index as u32 + 1).to_be_bytes());
+    let mut u = mac.finalize();
+    let mut t = u.clone();
+    for iteration in 1..iterations {
+      if iteration % CHECK_INTERVAL == 0 {
+        signals.check(&span)?;
+      }
+      let mut mac = prf.fork();
+      mac.update(&u);
+      u = mac.finalize();
+      t.iter_mut().zip(u.iter()).for_each(|(t, u)| *t ^= u);
+    }
+    block.copy_from_slice(&t[..block.len()]);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+
+  use super::*;
+
+  #[test]
+  fn test_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash pbkdf2", HashesPlugin.into())?
+      .test_examples(&Pbkdf2.examples())
+  }
+
+  #[cfg(feature = "sha1")]
+  #[test]
+  fn test_parameters() -> Result<(), ShellError> {
+    let mut plugin_test = PluginTest::new("hash pbkdf2", HashesPlugin.into())?;
+    let mut eval = |command: &str| {
+      plugin_test
+        .eval(command)
+        .and_then(|data| data.into_value(Span::test_data()))
+    };
+    let input = "'password' | hash pbkdf2 --salt salt";
+    assert!(eval(&format!("{input} -a nope -i 1")).is_err());
+    assert!(eval(&format!("{input} -a sha1 -i 0")).is_err());
+    assert!(eval(&format!("{input} -a sha1 -i 1 -l 0")).is_err());
+    assert!(
+      eval(&format!("{input} -a sha1 -i 1 -l {}", MAX_LENGTH + 1)).is_err()
+    );
+    assert!(eval(&format!("{input} -a sha1 -i 1 -l {}", u32::MAX)).is_err());
+    Ok(())
+  }
+
+  #[cfg(feature = "sha1")]
+  #[test]
+  fn test_interrupted() -> Result<(), LabeledError> {
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    let algorithm = Spanned {
+      item: "sha1".to_owned(),
+      span: Span::test_data(),
+    };
+    let prf = new_hmac(&algorithm, b"password")?;
+    let signals = Signals::new(Arc::new(AtomicBool::new(true)));
+    let mut key = vec![0; prf.output_size()];
+    let result = pbkdf2(
+      prf.as_ref(),
+      b"salt",
+      u32::MAX,
+      &mut key,
+      &signals,
+      Span::test_data(),
+    );
+    assert!(matches!(result, Err(ShellError::Interrupted { .. })));
+    Ok(())
+  }
+}
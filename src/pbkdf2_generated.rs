@@ -0,0 +1,2 @@
+#![allow(unused_imports)]
+include!(concat!(env!("OUT_DIR"), "/pbkdf2_generated.rs"));
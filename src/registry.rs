@@ -0,0 +1,158 @@
+//! Contains a runtime registry of compiled in hash algorithms, which allows
+//! to select them by name. The registry itself is generated by the build
+//! script from the same list as the `hash <algorithm>` commands.
+
+use digest::DynDigest;
+use nu_protocol::{LabeledError, Spanned};
+
+use crate::hasher::{DynHasher, Hasher};
+
+pub use crate::registry_generated::{ALGORITHMS, COMMAND_ALGORITHMS};
+
+/// Creates a hasher keyed with a key, or returns an error pointing at the key.
+pub type KeyedFn =
+  fn(&Spanned<Vec<u8>>) -> Result<Box<dyn DynHasher>, LabeledError>;
+
+pub struct Algorithm {
+  /// Name of the algorithm, same as its command name.
+  pub name: &'static str,
+  /// Name of the crate that implements the algorithm.
+  pub crate_name: &'static str,
+  /// Name of the type that implements the algorithm.
+  pub type_name: &'static str,
+  /// Cargo feature that enables the algorithm.
+  pub feature: &'static str,
+  /// Output size in bytes.
+  pub output_size: usize,
+  /// Block size in bytes.
+  pub block_size: usize,
+  /// Creates a new keyed hasher if the algorithm accepts a key. Algorithms
+  /// with a keyed mode of their own use it, others are turned into HMAC.
+  pub keyed: Option<KeyedFn>,
+  /// Whether the algorithm is an extendable-output function.
+  pub xof: bool,
+  pub security: Security,
+  /// Code of the algorithm in the multicodec table, if it has one.
+  pub multicodec: Option<u64>,
+  /// Creates a new hasher.
+  pub new: fn() -> Box<dyn DynHasher>,
+  /// Creates a new HMAC instance if the algorithm supports it.
+  pub hmac: fn(&[u8]) -> Option<Box<dyn DynHasher>>,
+}
+
+impl Algorithm {
+  /// Creates a new hasher keyed with `key`.
+  pub fn new_keyed(
+    &self,
+    key: &Spanned<Vec<u8>>,
+  ) -> Result<Box<dyn DynHasher>, LabeledError> {
+    let keyed = self.keyed.ok_or_else(|| {
+      LabeledError::new(format!("HMAC is not supported by {}", self.name))
+        .with_label("this algorithm can't be keyed", key.span)
+    })?;
+    keyed(key)
+  }
+}
+
+// Some variants are unused, depending on enabled features
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+pub enum Security {
+  /// Practical collision attacks are known.
+  Broken,
+  /// Not broken, but obsolete or with a small security margin.
+  Legacy,
+  Ok,
+}
+
+impl Security {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Broken => "broken",
+      Self::Legacy => "legacy",
+      Self::Ok => "ok",
+    }
+  }
+}
+
+pub fn new_hasher<D>() -> Box<dyn DynHasher>
+where
+  D: DynDigest + Clone + Default + Send + Sync + 'static,
+{
+  Box::new(D::default())
+}
+
+/// Creates an HMAC instance over `H` keyed with `key`.
+pub fn new_hmac<H: Hasher>(
+  key: &Spanned<Vec<u8>>,
+) -> Result<Box<dyn DynHasher>, LabeledError> {
+  H::hmac(&key.item).ok_or_else(|| {
+    LabeledError::new(format!("HMAC is not supported by {}", H::name()))
+      .with_label("this algorithm can't be keyed", key.span)
+  })
+}
+
+/// Returns an algorithm by its name.
+pub fn get(name: &str) -> Option<&'static Algorithm> {
+  ALGORITHMS.iter().find(|algorithm| algorithm.name == name)
+}
+
+/// Returns an algorithm by its name, or an error pointing at the name if
+/// there's no such algorithm.
+pub fn find(
+  name: &Spanned<String>,
+) -> Result<&'static Algorithm, LabeledError> {
+  get(&name.item).ok_or_else(|| {
+    LabeledError::new(format!("Unknown hash algorithm `{}`", name.item))
+      .with_label("algorithm not found", name.span)
+      .with_help(format!(
+        "available algorithms are: {}",
+        ALGORITHMS
+          .iter()
+          .map(|algorithm| algorithm.name)
+          .collect::<Vec<_>>()
+          .join(", ")
+      ))
+  })
+}
+
+#[cfg(all(test, feature = "blake3"))]
+mod tests {
+  use data_encoding::HEXLOWER;
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::{ShellError, Span, Value};
+
+  use super::*;
+  use crate::HashesPlugin;
+
+  #[test]
+  fn test_keyed_blake3() -> Result<(), ShellError> {
+    let digest =
+      "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26";
+    let key = |key: &str| Spanned {
+      item: key.as_bytes().to_vec(),
+      span: Span::test_data(),
+    };
+    let blake3 = get("blake3").unwrap();
+    let hasher = blake3.new_keyed(&key("whats the Elvish word for friend"))?;
+    assert_eq!(HEXLOWER.encode(&hasher.finalize()), digest);
+    assert!(blake3.new_keyed(&key("short")).is_err());
+
+    // Commands, that take algorithms by name, are keyed the same way as
+    // `hash blake3`
+    let mut plugin_test = PluginTest::new("hash digest", HashesPlugin.into())?;
+    let key = "--key 'whats the Elvish word for friend'";
+    for command in [
+      format!("'' | hash blake3 {key}"),
+      format!("'' | hash digest --algorithm blake3 {key}"),
+      format!("hash init blake3 {key} | hash update '' | hash finalize"),
+    ] {
+      assert_eq!(
+        plugin_test.eval(&command)?.into_value(Span::test_data())?,
+        Value::test_string(digest),
+        "{command}"
+      );
+    }
+    Ok(())
+  }
+}
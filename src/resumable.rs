@@ -0,0 +1,387 @@
+//! Contains SHA-1 and SHA-2 hashers, whose states can be exported to binary
+//! and imported back, so long hashes can be resumed after a restart.
+//!
+//! Hashers of RustCrypto don't expose their states with `digest` 0.10. The
+//! compression functions of `sha1` and `sha2` are public though, so these
+//! hashers are built on them, and only the padding is implemented here.
+
+use digest::{generic_array::GenericArray, DynDigest, InvalidBufferSize};
+
+/// Prefix of exported states.
+const MAGIC: &[u8] = b"nuhs";
+/// Version of the format of exported states.
+const VERSION: u8 = 1;
+
+/// Names of algorithms, whose states can be exported.
+pub const ALGORITHMS: &[&str] = &[
+  #[cfg(feature = "sha1")]
+  "sha1",
+  #[cfg(feature = "sha2")]
+  "sha224",
+  #[cfg(feature = "sha2")]
+  "sha384",
+  #[cfg(feature = "sha2")]
+  "sha512",
+  #[cfg(feature = "sha2")]
+  "sha512-224",
+  #[cfg(feature = "sha2")]
+  "sha512-256",
+];
+
+/// Chaining values of a compression function.
+#[derive(Clone)]
+enum Words {
+  #[cfg(feature = "sha1")]
+  Sha1([u32; 5]),
+  #[cfg(feature = "sha2")]
+  Sha256([u32; 8]),
+  #[cfg(feature = "sha2")]
+  Sha512([u64; 8]),
+}
+
+impl Words {
+  /// Returns initial values of an algorithm and its output size in bytes, as
+  /// specified in FIPS 180-4.
+  fn initial(name: &str) -> Option<(Self, usize)> {
+    match name {
+      #[cfg(feature = "sha1")]
+      "sha1" => Some((
+        Self::Sha1([
+          0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0,
+        ]),
+        20,
+      )),
+      #[cfg(feature = "sha2")]
+      "sha224" => Some((
+        Self::Sha256([
+          0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31,
+          0x68581511, 0x64f98fa7, 0xbefa4fa4,
+        ]),
+        28,
+      )),
+      #[cfg(feature = "sha2")]
+      "sha384" => Some((
+        Self::Sha512([
+          0xcbbb9d5dc1059ed8,
+          0x629a292a367cd507,
+          0x9159015a3070dd17,
+          0x152fecd8f70e5939,
+          0x67332667ffc00b31,
+          0x8eb44a8768581511,
+          0xdb0c2e0d64f98fa7,
+          0x47b5481dbefa4fa4,
+        ]),
+        48,
+      )),
+      #[cfg(feature = "sha2")]
+      "sha512" => Some((
+        Self::Sha512([
+          0x6a09e667f3bcc908,
+          0xbb67ae8584caa73b,
+          0x3c6ef372fe94f82b,
+          0xa54ff53a5f1d36f1,
+          0x510e527fade682d1,
+          0x9b05688c2b3e6c1f,
+          0x1f83d9abfb41bd6b,
+          0x5be0cd19137e2179,
+        ]),
+        64,
+      )),
+      #[cfg(feature = "sha2")]
+      "sha512-224" => Some((
+        Self::Sha512([
+          0x8c3d37c819544da2,
+          0x73e1996689dcd4d6,
+          0x1dfab7ae32ff9c82,
+          0x679dd514582f9fcf,
+          0x0f6d2b697bd44da8,
+          0x77e36f7304c48942,
+          0x3f9d85a86a1d36c8,
+          0x1112e6ad91d692a1,
+        ]),
+        28,
+      )),
+      #[cfg(feature = "sha2")]
+      "sha512-256" => Some((
+        Self::Sha512([
+          0x22312194fc2bf72c,
+          0x9f555fa3c84c64c2,
+          0x2393b86b6f53b151,
+          0x963877195940eabd,
+          0x96283ee2a88effe3,
+          0xbe5e1e2553863992,
+          0x2b0199fc2c85b8aa,
+          0x0eb72ddc81c52ca2,
+        ]),
+        32,
+      )),
+      _ => None,
+    }
+  }
+
+  fn block_size(&self) -> usize {
+    match self {
+      #[cfg(feature = "sha1")]
+      Self::Sha1(_) => 64,
+      #[cfg(feature = "sha2")]
+      Self::Sha256(_) => 64,
+      #[cfg(feature = "sha2")]
+      Self::Sha512(_) => 128,
+    }
+  }
+
+  /// Feeds a single block into the compression function.
+  fn compress(&mut self, block: &[u8]) {
+    match self {
+      #[cfg(feature = "sha1")]
+      Self::Sha1(words) => {
+        sha1::compress(words, &[*GenericArray::from_slice(block)])
+      }
+      #[cfg(feature = "sha2")]
+      Self::Sha256(words) => {
+        sha2::compress256(words, &[*GenericArray::from_slice(block)])
+      }
+      #[cfg(feature = "sha2")]
+      Self::Sha512(words) => {
+        sha2::compress512(words, &[*GenericArray::from_slice(block)])
+      }
+    }
+  }
+
+  /// Returns the words in big-endian order.
+  fn to_bytes(&self) -> Vec<u8> {
+    match self {
+      #[cfg(feature = "sha1")]
+      Self::Sha1(words) => words.iter().flat_map(|w| w.to_be_bytes()).collect(),
+      #[cfg(feature = "sha2")]
+      Self::Sha256(words) => {
+        words.iter().flat_map(|w| w.to_be_bytes()).collect()
+      }
+      #[cfg(feature = "sha2")]
+      Self::Sha512(words) => {
+        words.iter().flat_map(|w| w.to_be_bytes()).collect()
+      }
+    }
+  }
+
+  /// Replaces the words with big-endian ones of `bytes`, which must be as long
+  /// as `to_bytes` returns.
+  fn read_bytes(&mut self, bytes: &[u8]) {
+    match self {
+      #[cfg(feature = "sha1")]
+      Self::Sha1(words) => read_words(words, bytes, u32::from_be_bytes),
+      #[cfg(feature = "sha2")]
+      Self::Sha256(words) => read_words(words, bytes, u32::from_be_bytes),
+      #[cfg(feature = "sha2")]
+      Self::Sha512(words) => read_words(words, bytes, u64::from_be_bytes),
+    }
+  }
+}
+
+fn read_words<W, const N: usize>(
+  words: &mut [W],
+  bytes: &[u8],
+  from_bytes: fn([u8; N]) -> W,
+) {
+  for (word, bytes) in words.iter_mut().zip(bytes.chunks_exact(N)) {
+    *word = from_bytes(bytes.try_into().expect("chunks are N bytes long"));
+  }
+}
+
+/// A SHA-1 or SHA-2 hasher, that can be exported.
+#[derive(Clone)]
+pub struct ResumableHasher {
+  name: &'static str,
+  output_size: usize,
+  words: Words,
+  /// Input, that doesn't fill a block yet.
+  buffer: Vec<u8>,
+  /// Number of bytes fed into the hasher.
+  consumed: u64,
+}
+
+impl ResumableHasher {
+  /// Creates a hasher of an algorithm, if its states can be exported.
+  pub fn new(name: &str) -> Option<Self> {
+    let name = ALGORITHMS.iter().find(|algorithm| **algorithm == name)?;
+    let (words, output_size) = Words::initial(name)?;
+    Some(Self {
+      name,
+      output_size,
+      buffer: Vec::with_capacity(words.block_size()),
+      words,
+      consumed: 0,
+    })
+  }
+
+  pub fn name(&self) -> &'static str {
+    self.name
+  }
+
+  pub fn consumed(&self) -> u64 {
+    self.consumed
+  }
+
+  /// Serializes the state: the format version, the name of the algorithm,
+  /// the number of bytes consumed, chaining values and the buffered input.
+  pub fn export(&self) -> Vec<u8> {
+    let mut state = MAGIC.to_vec();
+    state.push(VERSION);
+    state.push(self.name.len() as u8);
+    state.extend_from_slice(self.name.as_bytes());
+    state.extend_from_slice(&self.consumed.to_be_bytes());
+    state.extend_from_slice(&self.words.to_bytes());
+    state.extend_from_slice(&self.buffer);
+    state
+  }
+
+  /// Deserializes a state exported by `export`. Returns an error message if
+  /// the state is malformed.
+  pub fn import(state: &[u8]) -> Result<Self, String> {
+    let state = state
+      .strip_prefix(MAGIC)
+      .ok_or("not an exported hasher state")?;
+    let (&version, state) = state.split_first().ok_or("state is truncated")?;
+    if version != VERSION {
+      return Err(format!("unsupported state version {version}"));
+    }
+    let (&length, state) = state.split_first().ok_or("state is truncated")?;
+    let (name, state) = state
+      .split_at_checked(length as usize)
+      .ok_or("state is truncated")?;
+    let name = std::str::from_utf8(name).map_err(|_| "invalid algorithm")?;
+    let mut hasher = Self::new(name)
+      .ok_or_else(|| format!("states of `{name}` can't be imported"))?;
+    let (consumed, state) =
+      state.split_first_chunk().ok_or("state is truncated")?;
+    let consumed = u64::from_be_bytes(*consumed);
+    let words_size = hasher.words.to_bytes().len();
+    let buffered = (consumed % hasher.words.block_size() as u64) as usize;
+    if state.len() != words_size + buffered {
+      return Err(format!(
+        "state of {name} must be {} bytes long",
+        MAGIC.len() + 2 + name.len() + 8 + words_size + buffered
+      ));
+    }
+    let (words, buffer) = state.split_at(words_size);
+    hasher.words.read_bytes(words);
+    hasher.buffer.extend_from_slice(buffer);
+    hasher.consumed = consumed;
+    Ok(hasher)
+  }
+
+  fn pad(&mut self) {
+    let block_size = self.words.block_size();
+    // Lengths take 64 bits with 64-byte blocks and 128 bits with 128-byte
+    // ones. SHA-1 and SHA-256 count bits modulo 2^64.
+    let length_size = block_size / 8;
+    let bits = (self.consumed as u128 * 8).to_be_bytes();
+    let mut padding = vec![0x80];
+    let padded = (self.buffer.len() + 1 + length_size) % block_size;
+    padding.resize(1 + (block_size - padded) % block_size, 0);
+    padding.extend_from_slice(&bits[bits.len() - length_size..]);
+    DynDigest::update(self, &padding);
+  }
+}
+
+impl DynDigest for ResumableHasher {
+  fn update(&mut self, mut data: &[u8]) {
+    self.consumed += data.len() as u64;
+    let block_size = self.words.block_size();
+    if !self.buffer.is_empty() {
+      let missing = (block_size - self.buffer.len()).min(data.len());
+      let (head, tail) = data.split_at(missing);
+      self.buffer.extend_from_slice(head);
+      data = tail;
+      if self.buffer.len() < block_size {
+        return;
+      }
+      self.words.compress(&self.buffer);
+      self.buffer.clear();
+    }
+    let mut blocks = data.chunks_exact(block_size);
+    for block in &mut blocks {
+      self.words.compress(block);
+    }
+    self.buffer.extend_from_slice(blocks.remainder());
+  }
+
+  fn finalize_into(mut self, buf: &mut [u8]) -> Result<(), InvalidBufferSize> {
+    self.finalize_into_reset(buf)
+  }
+
+  fn finalize_into_reset(
+    &mut self,
+    out: &mut [u8],
+  ) -> Result<(), InvalidBufferSize> {
+    if out.len() != self.output_size {
+      return Err(InvalidBufferSize);
+    }
+    self.pad();
+    out.copy_from_slice(&self.words.to_bytes()[..self.output_size]);
+    DynDigest::reset(self);
+    Ok(())
+  }
+
+  fn reset(&mut self) {
+    *self = Self::new(self.name).expect("the algorithm is resumable");
+  }
+
+  fn output_size(&self) -> usize {
+    self.output_size
+  }
+
+  fn box_clone(&self) -> Box<dyn DynDigest> {
+    Box::new(self.clone())
+  }
+}
+
+#[cfg(all(test, feature = "sha1", feature = "sha2"))]
+mod tests {
+  use digest::Digest;
+
+  use super::*;
+
+  fn check<D: Digest>(name: &str) {
+    // Lengths around block boundaries and padding, that spills over
+    for length in [0, 1, 55, 56, 63, 64, 65, 111, 112, 127, 128, 129, 1000] {
+      let data: Vec<u8> = (0..length).map(|i| i as u8).collect();
+      let mut hasher = ResumableHasher::new(name).unwrap();
+      // Updates that straddle blocks
+      for chunk in data.chunks(37) {
+        DynDigest::update(&mut hasher, chunk);
+      }
+      let digest = Box::new(hasher).finalize();
+      assert_eq!(*digest, *D::digest(&data), "{name} of {length} bytes");
+    }
+  }
+
+  #[test]
+  fn test_digests() {
+    check::<sha1::Sha1>("sha1");
+    check::<sha2::Sha224>("sha224");
+    check::<sha2::Sha384>("sha384");
+    check::<sha2::Sha512>("sha512");
+    check::<sha2::Sha512_224>("sha512-224");
+    check::<sha2::Sha512_256>("sha512-256");
+    assert!(ResumableHasher::new("sha3-256").is_none());
+  }
+
+  #[test]
+  fn test_export_import() {
+    let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+    let mut hasher = ResumableHasher::new("sha384").unwrap();
+    DynDigest::update(&mut hasher, &data[..200]);
+    let state = hasher.export();
+    let mut hasher = ResumableHasher::import(&state).unwrap();
+    assert_eq!(hasher.consumed(), 200);
+    DynDigest::update(&mut hasher, &data[200..]);
+    assert_eq!(*Box::new(hasher).finalize(), *sha2::Sha384::digest(&data));
+
+    assert!(ResumableHasher::import(&state[..state.len() - 1]).is_err());
+    assert!(
+      ResumableHasher::import(&[state.as_slice(), &[0]].concat()).is_err()
+    );
+    assert!(ResumableHasher::import(b"nope").is_err());
+  }
+}
@@ -0,0 +1,633 @@
+//! Contains the Skein command, that takes the state size and output length at
+//! runtime, as well as a key and a personalization string. The hashers
+//! generated by the build script only cover fixed combinations of state sizes
+//! and output lengths. Input handling mirrors the one of `GenericHasher`.
+//!
+//! The `skein` crate fixes the output length at compile time and has no
+//! keyed mode, so the hash is built from Threefish here, following the
+//! Skein 1.3 specification.
+
+use std::{io::Write, ops::Not};
+
+use nu_cmd_base::input_handler::CmdArgument;
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  ast::CellPath,
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+use threefish::{Threefish1024, Threefish256, Threefish512};
+
+use crate::{
+  encoding::{self, Encoding},
+  hasher::hash_value,
+  multihash,
+  parallel,
+  xof::MAX_LENGTH,
+  HashesPlugin,
+};
+
+/// Schema identifier "SHA3" and version 1 of the configuration block.
+const SCHEMA_VERSION: u64 = (1 << 32) | 0x3341_4853;
+const FLAG_FIRST: u64 = 1 << 62;
+const FLAG_FINAL: u64 = 1 << 63;
+const TYPE_KEY: u64 = 0;
+const TYPE_CONFIG: u64 = 4;
+const TYPE_PERSONALIZATION: u64 = 8;
+const TYPE_MESSAGE: u64 = 48;
+const TYPE_OUTPUT: u64 = 63;
+
+/// Size of the internal state of Skein, which is the block size as well.
+#[derive(Clone, Copy)]
+enum StateSize {
+  Skein256,
+  Skein512,
+  Skein1024,
+}
+
+impl StateSize {
+  fn from_bits(bits: i64) -> Option<Self> {
+    match bits {
+      256 => Some(Self::Skein256),
+      512 => Some(Self::Skein512),
+      1024 => Some(Self::Skein1024),
+      _ => None,
+    }
+  }
+
+  fn bytes(self) -> usize {
+    match self {
+      Self::Skein256 => 32,
+      Self::Skein512 => 64,
+      Self::Skein1024 => 128,
+    }
+  }
+
+  /// Multicodec code of one byte long digests. Codes of longer digests, up
+  /// to the state size, follow it.
+  fn multicodec(self) -> u64 {
+    match self {
+      Self::Skein256 => 0xb301,
+      Self::Skein512 => 0xb321,
+      Self::Skein1024 => 0xb361,
+    }
+  }
+
+  /// Encrypts `block` with Threefish of the matching size.
+  fn encrypt(self, key: &[u64], tweak: &[u64; 2], block: &mut [u64]) {
+    // Lengths always match the state size
+    match self {
+      Self::Skein256 => {
+        Threefish256::new_with_tweak_u64(key.try_into().unwrap(), tweak)
+          .encrypt_block_u64(block.try_into().unwrap())
+      }
+      Self::Skein512 => {
+        Threefish512::new_with_tweak_u64(key.try_into().unwrap(), tweak)
+          .encrypt_block_u64(block.try_into().unwrap())
+      }
+      Self::Skein1024 => {
+        Threefish1024::new_with_tweak_u64(key.try_into().unwrap(), tweak)
+          .encrypt_block_u64(block.try_into().unwrap())
+      }
+    }
+  }
+}
+
+/// Unique Block Iteration, the chaining mode all parts of Skein are built on.
+#[derive(Clone)]
+struct Ubi {
+  size: StateSize,
+  chain: Vec<u64>,
+  tweak: [u64; 2],
+  /// Bytes of the current block. A full block is only processed when more
+  /// data follows, as the last block has to be flagged.
+  block: Vec<u8>,
+}
+
+impl Ubi {
+  fn new(size: StateSize, chain: Vec<u64>, block_type: u64) -> Self {
+    Self {
+      size,
+      chain,
+      tweak: [0, FLAG_FIRST | block_type << 56],
+      block: Vec::with_capacity(size.bytes()),
+    }
+  }
+
+  fn update(&mut self, mut data: &[u8]) {
+    let block_size = self.size.bytes();
+    while !data.is_empty() {
+      if self.block.len() == block_size {
+        self.process_block();
+      }
+      let count = data.len().min(block_size - self.block.len());
+      self.block.extend_from_slice(&data[..count]);
+      data = &data[count..];
+    }
+  }
+
+  fn process_block(&mut self) {
+    self.tweak[0] += self.block.len() as u64;
+    self.block.resize(self.size.bytes(), 0);
+    let mut message = [0; 16];
+    for (word, bytes) in message.iter_mut().zip(self.block.chunks_exact(8)) {
+      *word = u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+    let mut words = message;
+    let words = &mut words[..self.chain.len()];
+    self.size.encrypt(&self.chain, &self.tweak, words);
+    for ((chain, word), message) in
+      self.chain.iter_mut().zip(words).zip(message)
+    {
+      *chain = *word ^ message;
+    }
+    self.tweak[1] &= !FLAG_FIRST;
+    self.block.clear();
+  }
+
+  fn finalize(mut self) -> Vec<u64> {
+    self.tweak[1] |= FLAG_FINAL;
+    self.process_block();
+    self.chain
+  }
+}
+
+/// Runs UBI over a whole message.
+fn ubi(
+  size: StateSize,
+  chain: Vec<u64>,
+  block_type: u64,
+  message: &[u8],
+) -> Vec<u64> {
+  let mut ubi = Ubi::new(size, chain, block_type);
+  ubi.update(message);
+  ubi.finalize()
+}
+
+/// Skein hasher with parameters known at runtime.
+#[derive(Clone)]
+pub struct Skein {
+  ubi: Ubi,
+  /// Output length in bytes.
+  length: usize,
+}
+
+impl Skein {
+  fn new(
+    size: StateSize,
+    length: usize,
+    key: &[u8],
+    personalization: &[u8],
+  ) -> Self {
+    let mut chain = vec![0; size.bytes() / 8];
+    if !key.is_empty() {
+      chain = ubi(size, chain, TYPE_KEY, key);
+    }
+    let mut config = [0; 32];
+    config[..8].copy_from_slice(&SCHEMA_VERSION.to_le_bytes());
+    config[8..16].copy_from_slice(&(length as u64 * 8).to_le_bytes());
+    chain = ubi(size, chain, TYPE_CONFIG, &config);
+    if !personalization.is_empty() {
+      chain = ubi(size, chain, TYPE_PERSONALIZATION, personalization);
+    }
+    Self {
+      ubi: Ubi::new(size, chain, TYPE_MESSAGE),
+      length,
+    }
+  }
+
+  fn update(&mut self, data: &[u8]) {
+    self.ubi.update(data);
+  }
+
+  fn finalize(self) -> Vec<u8> {
+    let size = self.ubi.size;
+    let chain = self.ubi.finalize();
+    let mut output = Vec::with_capacity(self.length + size.bytes());
+    let mut counter: u64 = 0;
+    while output.len() < self.length {
+      let block = ubi(size, chain.clone(), TYPE_OUTPUT, &counter.to_le_bytes());
+      output.extend(block.iter().flat_map(|word| word.to_le_bytes()));
+      counter += 1;
+    }
+    output.truncate(self.length);
+    output
+  }
+}
+
+impl Write for Skein {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+pub struct SkeinHasher;
+
+struct Arguments {
+  cell_paths: Option<Vec<CellPath>>,
+  encoding: Encoding,
+  multihash: Option<u64>,
+  hasher: Skein,
+}
+
+impl CmdArgument for Arguments {
+  fn take_cell_paths(&mut self) -> Option<Vec<CellPath>> {
+    self.cell_paths.take()
+  }
+}
+
+impl PluginCommand for SkeinHasher {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash skein"
+  }
+
+  fn signature(&self) -> Signature {
+    parallel::signature(multihash::signature(encoding::signature(
+      Signature::build(self.name())
+        .category(Category::Hash)
+        .input_output_types(vec![
+          (Type::Binary, Type::Any),
+          (Type::String, Type::Any),
+          (Type::table(), Type::table()),
+          (Type::record(), Type::record()),
+        ])
+        .allow_variants_without_examples(true),
+    )))
+    .named(
+      "state-size",
+      SyntaxShape::Int,
+      "State size in bits: 256, 512 or 1024, 512 by default",
+      Some('s'),
+    )
+    .named(
+      "length",
+      SyntaxShape::Int,
+      "Output length in bits, a multiple of 8, the state size by default",
+      Some('l'),
+    )
+    .named(
+      "key",
+      SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+      "Key of a MAC",
+      Some('k'),
+    )
+    .named(
+      "personalization",
+      SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+      "Personalization string",
+      Some('p'),
+    )
+    .rest(
+      "rest",
+      SyntaxShape::CellPath,
+      "Optionally skein hash data by cell path.",
+    )
+  }
+
+  fn description(&self) -> &str {
+    "Hash a value using the skein hash algorithm with custom parameters."
+  }
+
+  fn extra_description(&self) -> &str {
+    "Unlike HMAC, keyed Skein is a MAC on its own. Skein-512 is recommended \
+     for all output lengths."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Return the skein-512-256 hash of a string",
+        example: "'The quick brown fox jumps over the lazy dog' | hash skein \
+                  --length 256",
+        result: Some(Value::string(
+          "b3250457e05d3060b1a4bbc1428bc75a3f525ca389aeab96cfa34638d96e492a",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the skein-512-160 hash of a string",
+        example: "'The quick brown fox jumps over the lazy dog' | hash skein \
+                  --length 160",
+        result: Some(Value::string(
+          "826325ee55a6dd18c3b2dbbc9c10420f5475975e",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the skein-256-256 hash of a byte (test vector \
+                      of the Skein specification)",
+        example: "0x[ff] | hash skein --state-size 256",
+        result: Some(Value::string(
+          "0b98dcd198ea0e50a7a244c444e25c23da30c10fc9a1f270a6637f1f34e67ed2",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the skein-512-256 MAC of a string, keyed with a \
+                      string",
+        example: "'The quick brown fox jumps over the lazy dog' | hash skein \
+                  --length 256 --key key",
+        result: Some(Value::string(
+          "b47f9abd80e84d66fc93bd4d7f19e610783436dba7c9713004d14be8ab467466",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the personalized skein-512-256 hash of a string",
+        example: "'The quick brown fox jumps over the lazy dog' | hash skein \
+                  --length 256 --personalization '20261018 me@example.com \
+                  app/v1'",
+        result: Some(Value::string(
+          "076030dd79facf111da2f0c7b7bb2457be5286e7cb1c84d6faba9847d7e199e5",
+          Span::test_data(),
+        )),
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let encoding = Encoding::from_call(call)?;
+    let size = match call.get_flag::<Spanned<i64>>("state-size")? {
+      Some(size) => StateSize::from_bits(size.item).ok_or_else(|| {
+        LabeledError::new("Invalid state size")
+          .with_label("state size must be 256, 512 or 1024 bits", size.span)
+      })?,
+      None => StateSize::Skein512,
+    };
+    let bits = match call.get_flag::<Spanned<i64>>("length")? {
+      Some(length) => usize::try_from(length.item)
+        .ok()
+        .filter(|length| {
+          (1..=MAX_LENGTH * 8).contains(length) && length % 8 == 0
+        })
+        .ok_or_else(|| {
+          LabeledError::new("Invalid output length").with_label(
+            format!(
+              "output length must be a multiple of 8 from 8 to {} bits",
+              MAX_LENGTH * 8
+            ),
+            length.span,
+          )
+        })?,
+      None => size.bytes() * 8,
+    };
+    let length = bits / 8;
+    let key: Vec<u8> = call.get_flag("key")?.unwrap_or_default();
+    let personalization: Vec<u8> =
+      call.get_flag("personalization")?.unwrap_or_default();
+    let multihash = if length > size.bytes() {
+      multihash::from_call(call, "skein with output above state size", None)?
+    } else if !personalization.is_empty() {
+      multihash::from_call(call, "personalized skein", None)?
+    } else {
+      let code = size.multicodec() + length as u64 - 1;
+      multihash::from_call(call, "skein", Some(code))?
+    };
+    let mut hasher = Skein::new(size, length, &key, &personalization);
+    let cell_paths: Vec<CellPath> = call.rest(0)?;
+    let cell_paths = cell_paths.is_empty().not().then_some(cell_paths);
+
+    if let PipelineData::ByteStream(stream, ..) = input {
+      stream.write_to(&mut hasher)?;
+      let digest = hasher.finalize();
+      let digest = match multihash {
+        Some(code) => multihash::encode(code, digest),
+        None => digest,
+      };
+      Ok(encoding.encode(digest, head).into_pipeline_data())
+    } else {
+      parallel::operate(
+        action,
+        Arguments {
+          cell_paths,
+          encoding,
+          multihash,
+          hasher,
+        },
+        input,
+        head,
+        engine.signals(),
+        parallel::from_call(engine, call)?,
+      )
+      .map_err(Into::into)
+    }
+  }
+}
+
+fn action(input: &Value, args: &Arguments, _span: Span) -> Value {
+  hash_value(input, args.encoding, |bytes| {
+    let mut hasher = args.hasher.clone();
+    hasher.update(bytes);
+    let digest = hasher.finalize();
+    match args.multihash {
+      Some(code) => multihash::encode(code, digest),
+      None => digest,
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::ShellError;
+  use skein::{consts::*, Digest};
+
+  use super::*;
+
+  #[test]
+  fn test_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash skein", HashesPlugin.into())?
+      .test_examples(&SkeinHasher.examples())
+  }
+
+  fn digest(size: StateSize, length: usize, data: &[u8]) -> Vec<u8> {
+    let mut hasher = Skein::new(size, length, &[], &[]);
+    hasher.update(data);
+    hasher.finalize()
+  }
+
+  #[test]
+  fn test_skein() {
+    // Empty, partial, full and several blocks, as they are flagged
+    // differently
+    let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+    for length in [0, 1, 32, 64, 128, 129, 1000] {
+      let data = &data[..length];
+      assert_eq!(
+        digest(StateSize::Skein256, 32, data),
+        skein::Skein256::<U32>::digest(data).to_vec()
+      );
+      assert_eq!(
+        digest(StateSize::Skein256, 64, data),
+        skein::Skein256::<U64>::digest(data).to_vec()
+      );
+      assert_eq!(
+        digest(StateSize::Skein512, 20, data),
+        skein::Skein512::<U20>::digest(data).to_vec()
+      );
+      assert_eq!(
+        digest(StateSize::Skein512, 64, data),
+        skein::Skein512::<U64>::digest(data).to_vec()
+      );
+      assert_eq!(
+        digest(StateSize::Skein1024, 48, data),
+        skein::Skein1024::<U48>::digest(data).to_vec()
+      );
+      assert_eq!(
+        digest(StateSize::Skein1024, 128, data),
+        skein::Skein1024::<U128>::digest(data).to_vec()
+      );
+    }
+  }
+
+  /// Skein-512 of a key, personalization string and message, that all fit
+  /// into a single block, computed straight from Threefish with the block
+  /// types and tweak layout of the specification.
+  fn single_block_skein512(
+    key: &[u8],
+    personalization: &[u8],
+    message: &[u8],
+    length: usize,
+  ) -> Vec<u8> {
+    let block = |chain: [u64; 8], block_type: u64, data: &[u8]| {
+      let mut bytes = [0; 64];
+      bytes[..data.len()].copy_from_slice(data);
+      let mut message = [0; 8];
+      for (word, bytes) in message.iter_mut().zip(bytes.chunks_exact(8)) {
+        *word = u64::from_le_bytes(bytes.try_into().unwrap());
+      }
+      // Position, block type, first and final flags
+      let tweak = [data.len() as u64, block_type << 56 | 3 << 62];
+      let mut output = message;
+      Threefish512::new_with_tweak_u64(&chain, &tweak)
+        .encrypt_block_u64(&mut output);
+      output.iter_mut().zip(message).for_each(|(o, m)| *o ^= m);
+      output
+    };
+    let mut chain = [0; 8];
+    if !key.is_empty() {
+      chain = block(chain, 0, key);
+    }
+    let mut config = b"SHA3\x01\x00\x00\x00".to_vec();
+    config.extend((length as u64 * 8).to_le_bytes());
+    config.extend([0; 16]);
+    chain = block(chain, 4, &config);
+    if !personalization.is_empty() {
+      chain = block(chain, 8, personalization);
+    }
+    chain = block(chain, 48, message);
+    let output = block(chain, 63, &0u64.to_le_bytes());
+    let output: Vec<u8> = output.iter().flat_map(|w| w.to_le_bytes()).collect();
+    output[..length].to_vec()
+  }
+
+  #[test]
+  fn test_mac_and_personalization() {
+    let data: Vec<u8> = (0..64).map(|i| 0xff - i as u8).collect();
+    let key: Vec<u8> = (0..64).map(|i| i as u8).collect();
+    // The reference matches the `skein` crate without key and
+    // personalization
+    for length in [0, 1, 64] {
+      assert_eq!(
+        single_block_skein512(&[], &[], &data[..length], 64),
+        skein::Skein512::<U64>::digest(&data[..length]).to_vec()
+      );
+    }
+    for (key, personalization) in [
+      (&key[..16], &[][..]),
+      (&key[..32], &[][..]),
+      (&key[..64], &[][..]),
+      (&[][..], &b"20261018 me@example.com app/v1"[..]),
+      (&key[..32], &b"20261018 me@example.com app/v1"[..]),
+    ] {
+      for length in [0, 1, 64] {
+        for output_length in [20, 32, 64] {
+          let mut hasher = Skein::new(
+            StateSize::Skein512,
+            output_length,
+            key,
+            personalization,
+          );
+          hasher.update(&data[..length]);
+          assert_eq!(
+            hasher.finalize(),
+            single_block_skein512(
+              key,
+              personalization,
+              &data[..length],
+              output_length
+            )
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_parameters() -> Result<(), ShellError> {
+    let mut plugin_test = PluginTest::new("hash skein", HashesPlugin.into())?;
+    let mut hash = |args: &str| {
+      plugin_test
+        .eval(&format!("'abc' | hash skein {args}"))
+        .and_then(|data| data.into_value(Span::test_data()))
+    };
+    assert!(hash("--state-size 128").is_err());
+    assert!(hash("--length 0").is_err());
+    assert!(hash("--length 255").is_err());
+    assert!(hash(&format!("--length {}", MAX_LENGTH * 8 + 8)).is_err());
+    assert!(hash("--length 9223372036854775800").is_err());
+    assert!(hash("--length 1024 --format multihash").is_err());
+    assert!(hash("--personalization p --format multihash").is_err());
+    assert_ne!(hash("--key a")?, hash("--key b")?);
+    assert_ne!(hash("--key a")?, hash("--personalization a")?);
+    assert_eq!(hash("--key ''")?, hash("")?);
+    // skein512-256 has code 0xb340, its varint takes 3 bytes
+    assert_eq!(
+      hash("--length 256 --format multihash")?
+        .into_string()?
+        .get(..8),
+      Some("c0e60220")
+    );
+    Ok(())
+  }
+
+  /// The row of `hash algorithms` is generated from the list in the build
+  /// script, check it against the default parameters.
+  #[test]
+  fn test_algorithm_row() {
+    let size = StateSize::Skein512;
+    let row = crate::registry::COMMAND_ALGORITHMS
+      .iter()
+      .find(|row| row.name == "skein")
+      .unwrap();
+    assert_eq!(row.output_size, size.bytes());
+    assert_eq!(row.block_size, size.bytes());
+    assert_eq!(
+      row.multicodec,
+      Some(size.multicodec() + size.bytes() as u64 - 1)
+    );
+  }
+}
@@ -0,0 +1,302 @@
+//! Contains a command that generates and verifies Subresource Integrity
+//! metadata, like `sha384-<base64>`, as specified by
+//! https://www.w3.org/TR/SRI/.
+
+use data_encoding::BASE64;
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  record,
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  ShellError,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+
+use crate::{
+  hasher::DynHasher,
+  multi::digest_input,
+  registry::new_hasher,
+  HashesPlugin,
+};
+
+type NewHasherFn = fn() -> Box<dyn DynHasher>;
+
+/// Algorithms allowed by SRI, from the weakest to the strongest.
+const ALGORITHMS: &[(&str, NewHasherFn)] = &[
+  ("sha256", new_hasher::<sha2::Sha256>),
+  ("sha384", new_hasher::<sha2::Sha384>),
+  ("sha512", new_hasher::<sha2::Sha512>),
+];
+
+const DEFAULT_ALGORITHM: &str = "sha384";
+
+pub struct Sri;
+
+impl PluginCommand for Sri {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash sri"
+  }
+
+  fn signature(&self) -> Signature {
+    Signature::build(self.name())
+      .category(Category::Hash)
+      .input_output_types(vec![
+        (Type::Binary, Type::String),
+        (Type::String, Type::String),
+        (Type::Binary, Type::record()),
+        (Type::String, Type::record()),
+      ])
+      .named(
+        "algorithm",
+        SyntaxShape::OneOf(vec![
+          SyntaxShape::List(Box::new(SyntaxShape::String)),
+          SyntaxShape::String,
+        ]),
+        "Algorithms of generated tokens: sha256, sha384 (default) or sha512",
+        Some('a'),
+      )
+      .named(
+        "verify",
+        SyntaxShape::String,
+        "Verify the input against an integrity string instead",
+        Some('v'),
+      )
+  }
+
+  fn description(&self) -> &str {
+    "Generate or verify Subresource Integrity metadata."
+  }
+
+  fn extra_description(&self) -> &str {
+    "Without `--verify`, returns base64 tokens like `sha384-<digest>`, \
+     separated with spaces if several algorithms are given.
+
+With `--verify`, returns a record with the `valid` field and the token that \
+     matched. As the specification says, only tokens of the strongest \
+     algorithm in the integrity string are checked, and tokens of unknown \
+     algorithms are ignored."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Generate an integrity string of a script",
+        example: "\"alert('Hello, world.');\" | hash sri",
+        result: Some(Value::test_string(
+          "sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+\
+           eX6xO",
+        )),
+      },
+      Example {
+        description: "Generate an integrity string with several algorithms",
+        example: "'abcdefghijklmnopqrstuvwxyz' | hash sri --algorithm [sha256 \
+                  sha512]",
+        result: Some(Value::test_string(
+          "sha256-ccSA35PWri8e+tFEfGbJUl4xYhjPUfyNntgy8trxi3M= \
+           sha512-Tb/4bMLKG64eFkaKBcuYgcl/F1O842GQNImPqhqr5CmVWhv47Eg9dCH+\
+           PBZGYTpZ7VRB+w8yE4n3f0ioecex8Q==",
+        )),
+      },
+      Example {
+        description: "Verify a string against an integrity string",
+        example: "'abcdefghijklmnopqrstuvwxyz' | hash sri --verify \
+                  'sha256-ccSA35PWri8e+tFEfGbJUl4xYhjPUfyNntgy8trxi3M= \
+                  sha384-/rZzSd89tvWSSBXWw9wTPwkYCSE3Mf5ce19JmeRjR5/\
+                  yh39fKTb6Y7tDeEsS8+u0'",
+        result: Some(Value::test_record(record! {
+          "valid" => Value::test_bool(true),
+          "token" => Value::test_string(
+            "sha384-/rZzSd89tvWSSBXWw9wTPwkYCSE3Mf5ce19JmeRjR5/yh39fKTb6Y7tDeE\
+             sS8+u0",
+          ),
+        })),
+      },
+      Example {
+        description: "Generate an integrity attribute of a bundle",
+        example: "open --raw dist/app.js | hash sri --algorithm sha512",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let verify: Option<Spanned<String>> = call.get_flag("verify")?;
+    if let Some(integrity) = verify {
+      if let Some(value) = call.get_flag_value("algorithm") {
+        return Err(
+          LabeledError::new("Incompatible flags")
+            .with_label("can't be used with --verify", value.span()),
+        );
+      }
+      return Ok(
+        verify_integrity(input, &integrity, head)?.into_pipeline_data(),
+      );
+    }
+
+    let names = match call.get_flag_value("algorithm") {
+      Some(Value::List { vals, .. }) => vals
+        .into_iter()
+        .map(|name| {
+          let span = name.span();
+          Ok(Spanned {
+            item: name.into_string()?,
+            span,
+          })
+        })
+        .collect::<Result<Vec<_>, ShellError>>()?,
+      Some(name) => vec![Spanned {
+        item: name.clone().into_string()?,
+        span: name.span(),
+      }],
+      None => vec![Spanned {
+        item: DEFAULT_ALGORITHM.into(),
+        span: head,
+      }],
+    };
+    let mut algorithms = Vec::new();
+    for name in names {
+      let algorithm = ALGORITHMS
+        .iter()
+        .find(|(algorithm, _)| *algorithm == name.item)
+        .ok_or_else(|| {
+          LabeledError::new(format!(
+            "Unsupported SRI algorithm `{}`",
+            name.item
+          ))
+          .with_label("algorithm not allowed by SRI", name.span)
+          .with_help("SRI algorithms are sha256, sha384 and sha512")
+        })?;
+      if !algorithms.contains(algorithm) {
+        algorithms.push(*algorithm);
+      }
+    }
+
+    let digests = digest_input(
+      input,
+      algorithms.iter().map(|(_, new)| new()).collect(),
+      head,
+    )?;
+    let tokens: Vec<String> = algorithms
+      .iter()
+      .zip(digests)
+      .map(|((name, _), digest)| format!("{name}-{}", BASE64.encode(&digest)))
+      .collect();
+    Ok(Value::string(tokens.join(" "), head).into_pipeline_data())
+  }
+}
+
+/// Verifies input against tokens of the strongest algorithm in an integrity
+/// string.
+fn verify_integrity(
+  input: PipelineData,
+  integrity: &Spanned<String>,
+  head: Span,
+) -> Result<Value, LabeledError> {
+  // Tokens are `<algorithm>-<base64>`, optionally followed by `?<options>`
+  let tokens: Vec<(usize, &str, &str)> = integrity
+    .item
+    .split_ascii_whitespace()
+    .filter_map(|token| {
+      let (name, digest) = token.split_once('-')?;
+      let strength = ALGORITHMS.iter().position(|(a, _)| *a == name)?;
+      let digest = digest.split_once('?').map_or(digest, |(digest, _)| digest);
+      Some((strength, token, digest))
+    })
+    .collect();
+  let strongest = tokens
+    .iter()
+    .map(|(strength, ..)| *strength)
+    .max()
+    .ok_or_else(|| {
+      LabeledError::new("No supported integrity metadata")
+        .with_label("no sha256, sha384 or sha512 tokens", integrity.span)
+    })?;
+
+  let digest =
+    digest_input(input, vec![(ALGORITHMS[strongest].1)()], head)?.remove(0);
+  let token = tokens
+    .iter()
+    .filter(|(strength, ..)| *strength == strongest)
+    .find(|(.., expected)| {
+      BASE64
+        .decode(expected.as_bytes())
+        .is_ok_and(|e| e == digest)
+    })
+    .map(|(_, token, _)| *token);
+  Ok(Value::record(
+    record! {
+      "valid" => Value::bool(token.is_some(), head),
+      "token" => token.map_or(Value::nothing(head), |t| Value::string(t, head)),
+    },
+    head,
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::{ByteStream, Signals};
+
+  use super::*;
+
+  #[test]
+  fn test_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash sri", HashesPlugin.into())?
+      .test_examples(&Sri.examples())
+  }
+
+  #[test]
+  fn test_verify() -> Result<(), ShellError> {
+    let mut plugin_test = PluginTest::new("hash sri", HashesPlugin.into())?;
+    let mut verify = |integrity: &str| -> Result<Value, ShellError> {
+      let stream = ByteStream::read_binary(
+        b"abcdefghijklmnopqrstuvwxyz".to_vec(),
+        Span::test_data(),
+        Signals::empty(),
+      );
+      plugin_test
+        .eval_with(
+          &format!("hash sri --verify '{integrity}'"),
+          PipelineData::byte_stream(stream, None),
+        )?
+        .into_value(Span::test_data())
+    };
+
+    // Only the strongest algorithm counts, even if weaker tokens match
+    let result = verify(
+      "sha256-ccSA35PWri8e+tFEfGbJUl4xYhjPUfyNntgy8trxi3M= \
+       sha512-AAAA md5-AAAA",
+    )?;
+    assert_eq!(
+      result.get_data_by_key("valid"),
+      Some(Value::test_bool(false))
+    );
+    let result = verify(
+      "sha512-AAAA sha512-Tb/4bMLKG64eFkaKBcuYgcl/F1O842GQNImPqhqr5CmVWhv47Eg9\
+       dCH+PBZGYTpZ7VRB+w8yE4n3f0ioecex8Q==?ct=application/javascript",
+    )?;
+    assert_eq!(
+      result.get_data_by_key("valid"),
+      Some(Value::test_bool(true))
+    );
+    assert!(verify("md5-AAAA").is_err());
+    Ok(())
+  }
+}
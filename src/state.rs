@@ -0,0 +1,858 @@
+//! Contains commands that hash data incrementally: `hash init` creates a
+//! hasher state, `hash update` feeds data into it and `hash finalize` returns
+//! the digest.
+//!
+//! Custom values are serialized whenever they pass between the plugin and the
+//! engine, and hashers can't be serialized. So states are kept in the plugin
+//! and values only refer to them. `hash update` never changes a state, it
+//! creates a new one instead, which makes values behave like any other
+//! Nushell value: a state can be forked by updating it twice.
+//!
+//! States of SHA-1 and SHA-2 can also be exported to binary with `hash state
+//! export` and imported back, to resume long hashes after a restart.
+
+use std::{
+  collections::BTreeMap,
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+  },
+};
+
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  record,
+  Category,
+  CustomValue,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  ShellError,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(any(feature = "sha1", feature = "sha2"))]
+use crate::resumable::{self, ResumableHasher};
+use crate::{
+  encoding::{self, Encoding},
+  file,
+  hasher::DynHasher,
+  registry,
+  HashesPlugin,
+};
+
+/// Type name of hasher states, as shown by `describe`.
+const TYPE_NAME: &str = "hasher state";
+
+/// Hashers, that are referred to by `HasherState` values.
+static HASHERS: Mutex<BTreeMap<u64, Box<dyn DynHasher>>> =
+  Mutex::new(BTreeMap::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A reference to a hasher kept in the plugin.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HasherState {
+  /// Process id of the plugin, that keeps the hasher. States outlive the
+  /// plugin when it's restarted, and ids of a new process start over.
+  pid: u32,
+  id: u64,
+  algorithm: String,
+  keyed: bool,
+  /// Number of bytes fed into the hasher.
+  consumed: u64,
+}
+
+impl HasherState {
+  /// Keeps a hasher in the plugin and returns a state that refers to it.
+  fn new(
+    engine: &EngineInterface,
+    hasher: Box<dyn DynHasher>,
+    algorithm: &str,
+    keyed: bool,
+    consumed: u64,
+  ) -> Result<Self, ShellError> {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let mut hashers = HASHERS.lock().expect("hashers are never poisoned");
+    if hashers.is_empty() {
+      // The plugin must not be stopped while states refer to it
+      engine.set_gc_disabled(true)?;
+    }
+    hashers.insert(id, hasher);
+    Ok(Self {
+      pid: std::process::id(),
+      id,
+      algorithm: algorithm.to_owned(),
+      keyed,
+      consumed,
+    })
+  }
+
+  /// Reads a state from the input of a command.
+  fn from_input(input: PipelineData, head: Span) -> Result<Self, ShellError> {
+    let value = input.into_value(head)?;
+    let span = value.span();
+    match value {
+      Value::Custom { val, .. } => {
+        val.as_any().downcast_ref::<Self>().cloned().ok_or_else(|| {
+          ShellError::CantConvert {
+            to_type: TYPE_NAME.into(),
+            from_type: val.type_name(),
+            span,
+            help: Some("create a state with `hash init`".into()),
+          }
+        })
+      }
+      other => Err(ShellError::OnlySupportsThisInputType {
+        exp_input_type: TYPE_NAME.into(),
+        wrong_type: other.get_type().to_string(),
+        dst_span: head,
+        src_span: span,
+      }),
+    }
+  }
+
+  /// Returns a copy of the hasher, that the state refers to.
+  fn hasher(&self, span: Span) -> Result<Box<dyn DynHasher>, LabeledError> {
+    let hashers = HASHERS.lock().expect("hashers are never poisoned");
+    hashers
+      .get(&self.id)
+      .filter(|_| self.pid == std::process::id())
+      .map(|hasher| hasher.fork())
+      .ok_or_else(|| {
+        LabeledError::new("Hasher state is no longer available")
+          .with_label("this state belongs to a stopped plugin", span)
+          .with_help("start over with `hash init`")
+      })
+  }
+}
+
+#[typetag::serde]
+impl CustomValue for HasherState {
+  fn clone_value(&self, span: Span) -> Value {
+    Value::custom(Box::new(self.clone()), span)
+  }
+
+  fn type_name(&self) -> String {
+    TYPE_NAME.into()
+  }
+
+  fn to_base_value(&self, span: Span) -> Result<Value, ShellError> {
+    Ok(Value::record(
+      record! {
+        "algorithm" => Value::string(&self.algorithm, span),
+        "keyed" => Value::bool(self.keyed, span),
+        "consumed" => Value::filesize(self.consumed as i64, span),
+      },
+      span,
+    ))
+  }
+
+  fn as_any(&self) -> &dyn std::any::Any {
+    self
+  }
+
+  fn as_mut_any(&mut self) -> &mut dyn std::any::Any {
+    self
+  }
+
+  fn follow_path_string(
+    &self,
+    self_span: Span,
+    column_name: String,
+    path_span: Span,
+  ) -> Result<Value, ShellError> {
+    match column_name.as_str() {
+      "algorithm" => Ok(Value::string(&self.algorithm, path_span)),
+      "keyed" => Ok(Value::bool(self.keyed, path_span)),
+      "consumed" => Ok(Value::filesize(self.consumed as i64, path_span)),
+      _ => Err(ShellError::CantFindColumn {
+        col_name: column_name,
+        span: Some(path_span),
+        src_span: self_span,
+      }),
+    }
+  }
+
+  fn notify_plugin_on_drop(&self) -> bool {
+    true
+  }
+}
+
+/// Forgets the hasher of a state, that was dropped by the engine. Each state
+/// is sent to the engine once, so its hasher isn't referred to anymore.
+/// States of stopped plugins are ignored, as their ids may be reused.
+pub fn drop_state(
+  engine: &EngineInterface,
+  state: &HasherState,
+) -> Result<(), ShellError> {
+  if state.pid != std::process::id() {
+    return Ok(());
+  }
+  let mut hashers = HASHERS.lock().expect("hashers are never poisoned");
+  if hashers.remove(&state.id).is_some() && hashers.is_empty() {
+    engine.set_gc_disabled(false)?;
+  }
+  Ok(())
+}
+
+pub struct Init;
+
+impl PluginCommand for Init {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash init"
+  }
+
+  fn signature(&self) -> Signature {
+    Signature::build(self.name())
+      .category(Category::Hash)
+      .input_output_types(vec![(Type::Nothing, Type::Custom(TYPE_NAME.into()))])
+      .required(
+        "algorithm",
+        SyntaxShape::String,
+        "Name of the hash algorithm, same as the name of its command",
+      )
+      .named(
+        "key",
+        SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+        "Compute an HMAC keyed with the given string or binary, or a keyed \
+         BLAKE3 hash with a 32-byte key",
+        Some('k'),
+      )
+  }
+
+  fn description(&self) -> &str {
+    "Create a hasher state to hash data incrementally."
+  }
+
+  fn extra_description(&self) -> &str {
+    "Data is fed into the state with `hash update` and the digest is returned \
+     by `hash finalize`. States are immutable: updating a state returns a new \
+     one, so the same state can be updated with different data."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Hash a string in two parts",
+        example: "hash init sha512 | hash update 'abcdefghijklm' | hash \
+                  update 'nopqrstuvwxyz' | hash finalize",
+        result: Some(Value::test_string(
+          "4dbff86cc2ca1bae1e16468a05cb9881c97f1753bce3619034898faa1aabe429955a\
+           1bf8ec483d7421fe3c1646613a59ed5441fb0f321389f77f48a879c7b1f1",
+        )),
+      },
+      Example {
+        description: "Compute an HMAC incrementally",
+        example: "hash init sha512 --key Jefe | hash update 'what do ya \
+                  want ' | hash update 'for nothing?' | hash finalize",
+        result: Some(Value::test_string(
+          "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+           9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+        )),
+      },
+      Example {
+        description: "Hash the chunks of a stream",
+        example: "$chunks | reduce --fold (hash init sha3-256) {|chunk, state| \
+                  $state | hash update $chunk } | hash finalize",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    _input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let algorithm: Spanned<String> = call.req(0)?;
+    let algorithm = registry::find(&algorithm)?;
+    let key: Option<Spanned<Vec<u8>>> = call.get_flag("key")?;
+    let hasher = match &key {
+      Some(key) => algorithm.new_keyed(key)?,
+      // Resumable hashers compute the same digests, but can be exported
+      #[cfg(any(feature = "sha1", feature = "sha2"))]
+      None => match ResumableHasher::new(algorithm.name) {
+        Some(hasher) => Box::new(hasher),
+        None => (algorithm.new)(),
+      },
+      #[cfg(not(any(feature = "sha1", feature = "sha2")))]
+      None => (algorithm.new)(),
+    };
+    let state =
+      HasherState::new(engine, hasher, algorithm.name, key.is_some(), 0)?;
+    Ok(Value::custom(Box::new(state), call.head).into_pipeline_data())
+  }
+}
+
+pub struct Update;
+
+impl PluginCommand for Update {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash update"
+  }
+
+  fn signature(&self) -> Signature {
+    Signature::build(self.name())
+      .category(Category::Hash)
+      .input_output_types(vec![(
+        Type::Custom(TYPE_NAME.into()),
+        Type::Custom(TYPE_NAME.into()),
+      )])
+      .required(
+        "data",
+        SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+        "String or binary to feed into the hasher",
+      )
+  }
+
+  fn description(&self) -> &str {
+    "Feed data into a hasher state created with `hash init`."
+  }
+
+  fn extra_description(&self) -> &str {
+    "The input state is left as is, a new state is returned."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Hash a string and binary",
+        example: "hash init sha512 | hash update 'abcdefghijklm' | hash \
+                  update 0x[6e6f707172737475767778797a] | hash finalize",
+        result: Some(Value::test_string(
+          "4dbff86cc2ca1bae1e16468a05cb9881c97f1753bce3619034898faa1aabe429955a\
+           1bf8ec483d7421fe3c1646613a59ed5441fb0f321389f77f48a879c7b1f1",
+        )),
+      },
+      Example {
+        description: "Hash two files, that share a header",
+        example: "let header = hash init sha512 | hash update (open --raw \
+                  header.bin); [($header | hash update (open --raw a.bin) | \
+                  hash finalize) ($header | hash update (open --raw b.bin) | \
+                  hash finalize)]",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let state = HasherState::from_input(input, head)?;
+    let mut hasher = state.hasher(head)?;
+    let data: Value = call.req(0)?;
+    let bytes = match &data {
+      Value::String { val, .. } => val.as_bytes(),
+      Value::Binary { val, .. } => val.as_slice(),
+      other => {
+        return Err(
+          LabeledError::new("Invalid data")
+            .with_label("expected string or binary", other.span()),
+        )
+      }
+    };
+    hasher.update(bytes);
+    let state = HasherState::new(
+      engine,
+      hasher,
+      &state.algorithm,
+      state.keyed,
+      state.consumed + bytes.len() as u64,
+    )?;
+    Ok(Value::custom(Box::new(state), head).into_pipeline_data())
+  }
+}
+
+pub struct Finalize;
+
+impl PluginCommand for Finalize {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash finalize"
+  }
+
+  fn signature(&self) -> Signature {
+    encoding::signature(
+      Signature::build(self.name())
+        .category(Category::Hash)
+        .input_output_types(vec![
+          (Type::Custom(TYPE_NAME.into()), Type::String),
+          (Type::Custom(TYPE_NAME.into()), Type::Binary),
+        ]),
+    )
+  }
+
+  fn description(&self) -> &str {
+    "Return the digest of the data fed into a hasher state."
+  }
+
+  fn extra_description(&self) -> &str {
+    "The state can still be updated afterwards, to hash more data."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Return the digest of an empty string",
+        example: "hash init sha512 | hash finalize",
+        result: Some(Value::test_string(
+          "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0\
+           d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e",
+        )),
+      },
+      Example {
+        description: "Return the digest as binary",
+        example: "hash init sha512 | hash update 'abc' | hash finalize \
+                  --binary",
+        result: Some(Value::test_binary(vec![
+          0xdd, 0xaf, 0x35, 0xa1, 0x93, 0x61, 0x7a, 0xba, 0xcc, 0x41, 0x73,
+          0x49, 0xae, 0x20, 0x41, 0x31, 0x12, 0xe6, 0xfa, 0x4e, 0x89, 0xa9,
+          0x7e, 0xa2, 0x0a, 0x9e, 0xee, 0xe6, 0x4b, 0x55, 0xd3, 0x9a, 0x21,
+          0x92, 0x99, 0x2a, 0x27, 0x4f, 0xc1, 0xa8, 0x36, 0xba, 0x3c, 0x23,
+          0xa3, 0xfe, 0xeb, 0xbd, 0x45, 0x4d, 0x44, 0x23, 0x64, 0x3c, 0xe8,
+          0x0e, 0x2a, 0x9a, 0xc9, 0x4f, 0xa5, 0x4c, 0xa4, 0x9f,
+        ])),
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let encoding = Encoding::from_call(call)?;
+    let state = HasherState::from_input(input, head)?;
+    let digest = state.hasher(head)?.finalize().into_vec();
+    Ok(encoding.encode(digest, head).into_pipeline_data())
+  }
+}
+
+/// Reads a flag, that holds a number of bytes as an int or a filesize.
+fn byte_count(
+  call: &EvaluatedCall,
+  name: &str,
+) -> Result<Option<u64>, LabeledError> {
+  let Some(value) = call.get_flag_value(name) else {
+    return Ok(None);
+  };
+  let span = value.span();
+  let count = match value {
+    Value::Int { val, .. } => val,
+    Value::Filesize { val, .. } => val.get(),
+    other => {
+      return Err(LabeledError::new(format!("Invalid --{name}")).with_label(
+        format!("expected int or filesize, got {}", other.get_type()),
+        span,
+      ))
+    }
+  };
+  u64::try_from(count).map(Some).map_err(|_| {
+    LabeledError::new(format!("Invalid --{name}"))
+      .with_label(format!("--{name} can't be negative"), span)
+  })
+}
+
+pub struct UpdateFile;
+
+impl PluginCommand for UpdateFile {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash update file"
+  }
+
+  fn signature(&self) -> Signature {
+    Signature::build(self.name())
+      .category(Category::Hash)
+      .input_output_types(vec![(
+        Type::Custom(TYPE_NAME.into()),
+        Type::Custom(TYPE_NAME.into()),
+      )])
+      .required(
+        "path",
+        SyntaxShape::Filepath,
+        "File to feed into the hasher",
+      )
+      .named(
+        "offset",
+        SyntaxShape::OneOf(vec![SyntaxShape::Int, SyntaxShape::Filesize]),
+        "Start reading the file at this byte, 0 by default",
+        Some('o'),
+      )
+      .named(
+        "length",
+        SyntaxShape::OneOf(vec![SyntaxShape::Int, SyntaxShape::Filesize]),
+        "Read at most this many bytes, up to the end of the file by default",
+        Some('l'),
+      )
+  }
+
+  fn description(&self) -> &str {
+    "Feed a file, or a part of it, into a hasher state."
+  }
+
+  fn extra_description(&self) -> &str {
+    "The file is read by the plugin, so it doesn't pass through Nushell. With \
+     `--offset` and `--length` a large file can be hashed in parts, and the \
+     state can be saved with `hash state export` between them."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Hash a file",
+        example: "hash init sha512 | hash update file disk.img | hash finalize",
+        result: None,
+      },
+      Example {
+        description: "Hash the first gigabyte of a file and save the state",
+        example: "hash init sha512 | hash update file disk.img --length 1GiB \
+                  | hash state export | save disk.img.state",
+        result: None,
+      },
+      Example {
+        description: "Resume hashing a file from a saved state",
+        example: "let state = open --raw disk.img.state | hash state import; \
+                  $state | hash update file disk.img --offset $state.consumed \
+                  | hash finalize",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let state = HasherState::from_input(input, head)?;
+    let mut hasher = state.hasher(head)?;
+    let path: Spanned<String> = call.req(0)?;
+    let offset = byte_count(call, "offset")?.unwrap_or(0);
+    let length = byte_count(call, "length")?.unwrap_or(u64::MAX);
+
+    let span = path.span;
+    let path = match Path::new(&path.item).is_absolute() {
+      true => PathBuf::from(path.item),
+      false => PathBuf::from(engine.get_current_dir()?).join(path.item),
+    };
+    let read = file::hash_file(
+      &path,
+      &mut hasher,
+      offset,
+      length,
+      engine.signals(),
+      span,
+    )?;
+    let state = HasherState::new(
+      engine,
+      hasher,
+      &state.algorithm,
+      state.keyed,
+      state.consumed + read,
+    )?;
+    Ok(Value::custom(Box::new(state), head).into_pipeline_data())
+  }
+}
+
+#[cfg(any(feature = "sha1", feature = "sha2"))]
+pub struct Export;
+
+#[cfg(any(feature = "sha1", feature = "sha2"))]
+impl PluginCommand for Export {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash state export"
+  }
+
+  fn signature(&self) -> Signature {
+    Signature::build(self.name())
+      .category(Category::Hash)
+      .input_output_types(vec![(Type::Custom(TYPE_NAME.into()), Type::Binary)])
+  }
+
+  fn description(&self) -> &str {
+    "Export a hasher state to binary, so it can be imported after a restart."
+  }
+
+  fn extra_description(&self) -> &str {
+    "The binary holds the internal state of the hasher and the number of \
+     bytes it consumed. States of sha1, sha224, sha384, sha512, sha512-224 \
+     and sha512-256 can be exported. Other algorithms don't expose their \
+     states, and HMAC states aren't exported, as they would hold the key."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Save a state to a file",
+        example: "hash init sha512 | hash update 'abcdefghijklm' | hash state \
+                  export | save state.bin",
+        result: None,
+      },
+      Example {
+        description: "Export a state and import it back",
+        example: "hash init sha512 | hash update 'abcdefghijklm' | hash state \
+                  export | hash state import | hash update 'nopqrstuvwxyz' | \
+                  hash finalize",
+        result: Some(Value::test_string(
+          "4dbff86cc2ca1bae1e16468a05cb9881c97f1753bce3619034898faa1aabe429955a\
+           1bf8ec483d7421fe3c1646613a59ed5441fb0f321389f77f48a879c7b1f1",
+        )),
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let span = input.span().unwrap_or(head);
+    let state = HasherState::from_input(input, head)?;
+    if state.keyed {
+      return Err(
+        LabeledError::new("HMAC states can't be exported")
+          .with_label("this state holds a key", span)
+          .with_help("only states created without --key can be exported"),
+      );
+    }
+    let hasher = state.hasher(head)?;
+    let hasher = hasher
+      .as_any()
+      .downcast_ref::<ResumableHasher>()
+      .ok_or_else(|| {
+        LabeledError::new(format!(
+          "States of {} can't be exported",
+          state.algorithm
+        ))
+        .with_label("this algorithm doesn't expose its state", span)
+        .with_help(format!(
+          "states of these algorithms can be exported: {}",
+          resumable::ALGORITHMS.join(", ")
+        ))
+      })?;
+    Ok(Value::binary(hasher.export(), head).into_pipeline_data())
+  }
+}
+
+#[cfg(any(feature = "sha1", feature = "sha2"))]
+pub struct Import;
+
+#[cfg(any(feature = "sha1", feature = "sha2"))]
+impl PluginCommand for Import {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash state import"
+  }
+
+  fn signature(&self) -> Signature {
+    Signature::build(self.name())
+      .category(Category::Hash)
+      .input_output_types(vec![(Type::Binary, Type::Custom(TYPE_NAME.into()))])
+  }
+
+  fn description(&self) -> &str {
+    "Import a hasher state exported with `hash state export`."
+  }
+
+  fn extra_description(&self) -> &str {
+    "The imported state continues from where the exported one left off, its \
+     `consumed` column tells how many bytes were hashed."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![Example {
+      description: "Finish a hash from a saved state",
+      example: "open --raw state.bin | hash state import | hash update \
+                'nopqrstuvwxyz' | hash finalize",
+      result: None,
+    }]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let value = input.into_value(head)?;
+    let span = value.span();
+    let hasher =
+      ResumableHasher::import(value.as_binary()?).map_err(|error| {
+        LabeledError::new("Invalid hasher state").with_label(error, span)
+      })?;
+    let (algorithm, consumed) = (hasher.name(), hasher.consumed());
+    let state =
+      HasherState::new(engine, Box::new(hasher), algorithm, false, consumed)?;
+    Ok(Value::custom(Box::new(state), head).into_pipeline_data())
+  }
+}
+
+#[cfg(all(test, feature = "sha2"))]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+
+  use super::*;
+
+  #[test]
+  fn test_init_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash init", HashesPlugin.into())?
+      .test_examples(&Init.examples())
+  }
+
+  #[test]
+  fn test_update_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash update", HashesPlugin.into())?
+      .test_examples(&Update.examples())
+  }
+
+  #[test]
+  fn test_finalize_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash finalize", HashesPlugin.into())?
+      .test_examples(&Finalize.examples())
+  }
+
+  #[test]
+  fn test_export_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash state export", HashesPlugin.into())?
+      .test_examples(&Export.examples())?;
+    PluginTest::new("hash state import", HashesPlugin.into())?
+      .test_examples(&Import.examples())?;
+    PluginTest::new("hash update file", HashesPlugin.into())?
+      .test_examples(&UpdateFile.examples())
+  }
+
+  #[test]
+  fn test_export_import() -> Result<(), ShellError> {
+    let mut plugin_test =
+      PluginTest::new("hash state export", HashesPlugin.into())?;
+    let mut eval = |command: &str| {
+      plugin_test
+        .eval(command)
+        .and_then(|data| data.into_value(Span::test_data()))
+    };
+    let state = eval(
+      "hash init sha384 | hash update 'abcdefghijklm' | hash state export | \
+       hash state import",
+    )?;
+    let state = state.as_custom_value()?;
+    let state = state.as_any().downcast_ref::<HasherState>().unwrap();
+    assert_eq!(state.algorithm, "sha384");
+    assert!(!state.keyed);
+    assert_eq!(state.consumed, 13);
+
+    assert!(eval("hash init sha512 --key Jefe | hash state export").is_err());
+    #[cfg(feature = "sha3")]
+    assert!(eval("hash init sha3-256 | hash state export").is_err());
+    assert!(eval("0x[6e756873] | hash state import").is_err());
+    assert!(eval("0x[6e7568730106736861353132] | hash state import").is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_update_file() -> Result<(), ShellError> {
+    let dir = crate::file::TestDir::new("update_file");
+    let path = dir.join("alphabet.txt");
+    std::fs::write(&path, "abcdefghijklmnopqrstuvwxyz").unwrap();
+    let path = path.display();
+
+    let mut plugin_test =
+      PluginTest::new("hash update file", HashesPlugin.into())?;
+    let mut eval = |command: &str| {
+      plugin_test
+        .eval(command)
+        .and_then(|data| data.into_value(Span::test_data()))
+    };
+    let expected = Value::test_string(
+      "4dbff86cc2ca1bae1e16468a05cb9881c97f1753bce3619034898faa1aabe429955a\
+       1bf8ec483d7421fe3c1646613a59ed5441fb0f321389f77f48a879c7b1f1",
+    );
+    assert_eq!(
+      eval(&format!(
+        "hash init sha512 | hash update file '{path}' | hash \
+                     finalize"
+      ))?,
+      expected
+    );
+    // The second half is read after the state is exported and imported
+    assert_eq!(
+      eval(&format!(
+        "hash init sha512 | hash update file '{path}' --length 13 | hash \
+         state export | hash state import | hash update file '{path}' \
+         --offset 13B | hash finalize"
+      ))?,
+      expected
+    );
+    assert!(eval(&format!(
+      "hash init sha512 | hash update file '{path}' --offset (-1)"
+    ))
+    .is_err());
+    assert!(eval(&format!(
+      "hash init sha512 | hash update file '{}'",
+      dir.join("missing").display()
+    ))
+    .is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_fork() -> Result<(), ShellError> {
+    let mut plugin_test = PluginTest::new("hash update", HashesPlugin.into())?;
+    let forks = plugin_test
+      .eval(
+        "let state = hash init sha512 | hash update 'abcdefghijklm'; \
+         [($state | hash update 'nopqrstuvwxyz' | hash finalize) ($state | \
+         hash finalize) ($state | hash update 'nopqrstuvwxyz' | hash \
+         finalize)]",
+      )?
+      .into_value(Span::test_data())?;
+    let forks = forks.as_list()?;
+    assert_eq!(forks[0], forks[2]);
+    assert_ne!(forks[0], forks[1]);
+
+    let state = plugin_test
+      .eval("hash init sha512 --key Jefe | hash update 'abc'")?
+      .into_value(Span::test_data())?;
+    let state = state.as_custom_value()?;
+    let state = state.as_any().downcast_ref::<HasherState>().unwrap();
+    assert_eq!(state.algorithm, "sha512");
+    assert!(state.keyed);
+    assert_eq!(state.consumed, 3);
+    Ok(())
+  }
+}
@@ -0,0 +1,250 @@
+//! Contains a command that compares a digest or an HMAC tag with an expected
+//! value in constant time.
+
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  Signature,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+use subtle::ConstantTimeEq;
+
+use crate::{
+  encoding::{self, Encoding},
+  multi::digest_input,
+  registry,
+  HashesPlugin,
+};
+
+pub struct Verify;
+
+impl PluginCommand for Verify {
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    "hash verify"
+  }
+
+  fn signature(&self) -> Signature {
+    Signature::build(self.name())
+      .category(Category::Hash)
+      .input_output_types(vec![
+        (Type::Binary, Type::Bool),
+        (Type::String, Type::Bool),
+      ])
+      .required_named(
+        "algorithm",
+        SyntaxShape::String,
+        "Name of the hash algorithm, same as the name of its command",
+        Some('a'),
+      )
+      .required_named(
+        "expected",
+        SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+        "Expected digest, as binary or as an encoded string",
+        Some('x'),
+      )
+      .named(
+        "encoding",
+        SyntaxShape::String,
+        "Encoding of the expected digest, detected by default",
+        Some('e'),
+      )
+      .named(
+        "key",
+        SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+        "Verify an HMAC keyed with the given string or binary, or a keyed \
+         BLAKE3 hash with a 32-byte key",
+        Some('k'),
+      )
+      .switch(
+        "allow-truncated",
+        "Accept an expected digest shorter than the algorithm output",
+        None,
+      )
+      .switch("error", "Fail instead of returning false on mismatch", None)
+  }
+
+  fn description(&self) -> &str {
+    "Check that a value hashes to the expected digest, in constant time."
+  }
+
+  fn extra_description(&self) -> &str {
+    "Unlike comparing digests with `==`, the comparison takes the same time \
+     wherever the digests differ, so it's safe for HMAC tags. Without \
+     `--encoding`, expected strings are decoded as hex, base64, base64url, \
+     base32, nix32 or base58, whichever gives a digest of the right length.
+
+The expected digest must be as long as the algorithm output, unless \
+     `--allow-truncated` is set. Then it's compared with as many leading \
+     bytes of the digest."
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    vec![
+      Example {
+        description: "Verify a sha512 digest",
+        example: "'abcdefghijklmnopqrstuvwxyz' | hash verify --algorithm \
+                  sha512 --expected \
+                  4dbff86cc2ca1bae1e16468a05cb9881c97f1753bce3619034898faa1aabe4\
+                  29955a1bf8ec483d7421fe3c1646613a59ed5441fb0f321389f77f48a879c7\
+                  b1f1",
+        result: Some(Value::test_bool(true)),
+      },
+      Example {
+        description: "Verify a base64-encoded HMAC tag",
+        example: "'what do ya want for nothing?' | hash verify --algorithm \
+                  sha512 --key Jefe --expected \
+                  Fkt6e/z4GeLjlfvnO1bgo4e9ZCIugx/WECcM1+olBVSXWL91wFqZSm0DT2X4\
+                  8Ob9yuqxo01Ka0tjbgcKOLznNw==",
+        result: Some(Value::test_bool(true)),
+      },
+      Example {
+        description: "Verify an HMAC tag truncated to 128 bits",
+        example: "'what do ya want for nothing?' | hash verify --algorithm \
+                  sha512 --key Jefe --expected \
+                  0x[164b7a7bfcf819e2e395fbe73b56e0a3] --allow-truncated",
+        result: Some(Value::test_bool(true)),
+      },
+      Example {
+        description: "Fail if a downloaded file doesn't match its digest",
+        example: "open --raw release.tar.gz | hash verify --algorithm \
+                  sha3-256 --expected $digest --error",
+        result: None,
+      },
+    ]
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let algorithm: Spanned<String> =
+      call.get_flag("algorithm")?.ok_or_else(|| {
+        LabeledError::new("Missing algorithm").with_label("", head)
+      })?;
+    let algorithm = registry::find(&algorithm)?;
+    let expected = call.get_flag_value("expected").ok_or_else(|| {
+      LabeledError::new("Missing expected digest").with_label("", head)
+    })?;
+    let expected_span = expected.span();
+    let encoding: Option<Spanned<String>> = call.get_flag("encoding")?;
+    let encoding = encoding.as_ref().map(Encoding::find).transpose()?;
+    let key: Option<Spanned<Vec<u8>>> = call.get_flag("key")?;
+    let allow_truncated = call.has_flag("allow-truncated")?;
+    let error = call.has_flag("error")?;
+
+    let output_size = algorithm.output_size;
+    let valid_length = |length: usize| {
+      length == output_size
+        || (allow_truncated && length > 0 && length < output_size)
+    };
+    let expected = match (expected, encoding) {
+      (Value::Binary { val, .. }, _) => val,
+      (Value::String { val, .. }, Some(encoding)) => {
+        encoding.decode(&val).ok_or_else(|| {
+          LabeledError::new("Invalid expected digest")
+            .with_label("can't be decoded with this encoding", expected_span)
+        })?
+      }
+      (Value::String { val, .. }, None) => {
+        encoding::decode_any(&val, |bytes| valid_length(bytes.len()))
+          .ok_or_else(|| {
+            LabeledError::new("Invalid expected digest").with_label(
+              format!("not an encoded {output_size} bytes digest"),
+              expected_span,
+            )
+          })?
+      }
+      (other, _) => {
+        return Err(
+          LabeledError::new("Invalid expected digest")
+            .with_label("expected string or binary", other.span()),
+        )
+      }
+    };
+    if !valid_length(expected.len()) {
+      return Err(
+        LabeledError::new("Invalid expected digest length")
+          .with_label(
+            format!(
+              "{} bytes long, but {} digests are {output_size} bytes long",
+              expected.len(),
+              algorithm.name
+            ),
+            expected_span,
+          )
+          .with_help(if expected.len() < output_size {
+            "use --allow-truncated to compare truncated digests"
+          } else {
+            "the expected digest may belong to another algorithm"
+          }),
+      );
+    }
+
+    let hasher = match &key {
+      Some(key) => algorithm.new_keyed(key)?,
+      None => (algorithm.new)(),
+    };
+    let digest = digest_input(input, vec![hasher], head)?.remove(0);
+    let matches: bool = digest[..expected.len()].ct_eq(&expected).into();
+
+    if !matches && error {
+      return Err(
+        LabeledError::new("Digest mismatch")
+          .with_label("input doesn't hash to this digest", expected_span),
+      );
+    }
+    Ok(Value::bool(matches, head).into_pipeline_data())
+  }
+}
+
+#[cfg(all(test, feature = "sha2"))]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::{ShellError, Span};
+
+  use super::*;
+
+  #[test]
+  fn test_examples() -> Result<(), ShellError> {
+    PluginTest::new("hash verify", HashesPlugin.into())?
+      .test_examples(&Verify.examples())
+  }
+
+  #[test]
+  fn test_errors() -> Result<(), ShellError> {
+    let mut plugin_test = PluginTest::new("hash verify", HashesPlugin.into())?;
+    let mut verify = |args: &str| {
+      plugin_test
+        .eval(&format!("'abc' | hash verify --algorithm sha512 {args}"))
+        .and_then(|data| data.into_value(Span::test_data()))
+    };
+    // Truncated digests are rejected unless allowed
+    assert!(verify("--expected 0x[ddaf35a1]").is_err());
+    assert_eq!(
+      verify("--expected 0x[ddaf35a1] --allow-truncated")?,
+      Value::test_bool(true)
+    );
+    assert_eq!(
+      verify("--expected 0x[ddaf35a0] --allow-truncated")?,
+      Value::test_bool(false)
+    );
+    assert!(
+      verify("--expected 0x[ddaf35a0] --allow-truncated --error").is_err()
+    );
+    assert!(verify("--expected 'ddaf35a1!' --encoding base64").is_err());
+    Ok(())
+  }
+}
@@ -0,0 +1,793 @@
+//! Contains a generic trait for extendable-output functions, that can produce
+//! digests of arbitrary length, and a command built around it. Input handling
+//! mirrors the one of `GenericHasher`.
+
+#![cfg_attr(not(any(feature = "sha3", feature = "blake3")), allow(dead_code))]
+
+use std::{io::Write, marker::PhantomData, ops::Not};
+
+use digest::Update;
+use nu_cmd_base::input_handler::CmdArgument;
+use nu_plugin::{EngineInterface, EvaluatedCall, PluginCommand};
+use nu_protocol::{
+  ast::CellPath,
+  ByteStream,
+  Category,
+  Example,
+  IntoPipelineData,
+  LabeledError,
+  PipelineData,
+  ShellError,
+  Signature,
+  Span,
+  Spanned,
+  SyntaxShape,
+  Type,
+  Value,
+};
+
+use crate::{
+  encoding::{self, Encoding},
+  hasher::hash_value,
+  multihash,
+  parallel,
+  HashesPlugin,
+};
+
+/// Largest output length in bytes. Outputs are kept in memory and encoded as a
+/// whole, so longer ones could exhaust it.
+pub const MAX_LENGTH: usize = 16 * 1024 * 1024;
+
+pub trait XofHasher: Update + Clone {
+  /// Output length in bytes used when `--length` is not specified.
+  const DEFAULT_LENGTH: usize;
+  /// Code of the algorithm in the multicodec table, if it has one.
+  const MULTICODEC: Option<u64> = None;
+
+  fn name() -> &'static str;
+  fn examples() -> Vec<Example<'static>>;
+
+  /// Adds algorithm specific parameters to the command signature.
+  fn signature(signature: Signature) -> Signature {
+    signature
+  }
+
+  /// Creates a hasher configured with algorithm specific parameters.
+  fn from_call(call: &EvaluatedCall) -> Result<Self, LabeledError>;
+
+  /// Feeds a byte stream into the hasher.
+  fn update_stream(
+    &mut self,
+    stream: ByteStream,
+    _span: Span,
+  ) -> Result<(), ShellError>
+  where
+    Self: Write + Sized,
+  {
+    stream.write_to(self)
+  }
+
+  /// Fills `output` with the output stream of the hasher.
+  fn finalize_into(self, output: &mut [u8]);
+}
+
+pub fn commands() -> Vec<Box<dyn PluginCommand<Plugin = HashesPlugin>>> {
+  vec![
+    #[cfg(feature = "sha3")]
+    Box::new(GenericXofHasher::<sha3::Shake128>::default()),
+    #[cfg(feature = "sha3")]
+    Box::new(GenericXofHasher::<sha3::Shake256>::default()),
+    #[cfg(feature = "sha3")]
+    Box::new(GenericXofHasher::<sha3::CShake128>::default()),
+    #[cfg(feature = "sha3")]
+    Box::new(GenericXofHasher::<sha3::CShake256>::default()),
+    #[cfg(feature = "blake3")]
+    Box::new(GenericXofHasher::<Blake3>::default()),
+  ]
+}
+
+#[derive(Clone)]
+pub struct GenericXofHasher<H: XofHasher> {
+  name: String,
+  description: String,
+  _hasher: PhantomData<H>,
+}
+
+impl<H: XofHasher> Default for GenericXofHasher<H> {
+  fn default() -> Self {
+    Self {
+      name: format!("hash {}", H::name()),
+      description: format!(
+        "Hash a value using the {} extendable-output function.",
+        H::name()
+      ),
+      _hasher: PhantomData,
+    }
+  }
+}
+
+struct Arguments<H> {
+  cell_paths: Option<Vec<CellPath>>,
+  encoding: Encoding,
+  multihash: Option<u64>,
+  length: usize,
+  hasher: H,
+}
+
+impl<H> CmdArgument for Arguments<H> {
+  fn take_cell_paths(&mut self) -> Option<Vec<CellPath>> {
+    self.cell_paths.take()
+  }
+}
+
+impl<H> PluginCommand for GenericXofHasher<H>
+where
+  H: XofHasher + Write + Send + Sync + 'static,
+{
+  type Plugin = HashesPlugin;
+
+  fn name(&self) -> &str {
+    &self.name
+  }
+
+  fn signature(&self) -> Signature {
+    H::signature(
+      parallel::signature(multihash::signature(encoding::signature(
+        Signature::build(self.name())
+          .category(Category::Hash)
+          .input_output_types(vec![
+            (Type::Binary, Type::Any),
+            (Type::String, Type::Any),
+            (Type::table(), Type::table()),
+            (Type::record(), Type::record()),
+          ])
+          .allow_variants_without_examples(true),
+      )))
+      .named(
+        "length",
+        SyntaxShape::Int,
+        format!("Output length in bytes, {} by default", H::DEFAULT_LENGTH),
+        Some('l'),
+      ),
+    )
+    .rest(
+      "rest",
+      SyntaxShape::CellPath,
+      format!("Optionally {} hash data by cell path.", H::name()),
+    )
+  }
+
+  fn description(&self) -> &str {
+    &self.description
+  }
+
+  fn examples(&self) -> Vec<Example<'_>> {
+    H::examples()
+  }
+
+  fn run(
+    &self,
+    _plugin: &HashesPlugin,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+  ) -> Result<PipelineData, LabeledError> {
+    let head = call.head;
+    let encoding = Encoding::from_call(call)?;
+    let length = match call.get_flag::<Spanned<i64>>("length")? {
+      Some(length) => usize::try_from(length.item)
+        .ok()
+        .filter(|length| (1..=MAX_LENGTH).contains(length))
+        .ok_or_else(|| {
+          LabeledError::new("Invalid output length").with_label(
+            format!("output length must be from 1 to {MAX_LENGTH} bytes"),
+            length.span,
+          )
+        })?,
+      None => H::DEFAULT_LENGTH,
+    };
+    let multihash = multihash::from_call(call, H::name(), H::MULTICODEC)?;
+    let hasher = H::from_call(call)?;
+    let cell_paths: Vec<CellPath> = call.rest(0)?;
+    let cell_paths = cell_paths.is_empty().not().then_some(cell_paths);
+
+    if let PipelineData::ByteStream(stream, ..) = input {
+      let mut hasher = hasher;
+      hasher.update_stream(stream, head)?;
+      let mut digest = vec![0; length];
+      hasher.finalize_into(&mut digest);
+      let digest = match multihash {
+        Some(code) => multihash::encode(code, digest),
+        None => digest,
+      };
+      Ok(encoding.encode(digest, head).into_pipeline_data())
+    } else {
+      parallel::operate(
+        action::<H>,
+        Arguments {
+          cell_paths,
+          encoding,
+          multihash,
+          length,
+          hasher,
+        },
+        input,
+        head,
+        engine.signals(),
+        parallel::from_call(engine, call)?,
+      )
+      .map_err(Into::into)
+    }
+  }
+}
+
+fn action<H>(input: &Value, args: &Arguments<H>, _span: Span) -> Value
+where
+  H: XofHasher,
+{
+  hash_value(input, args.encoding, |bytes| {
+    let mut hasher = args.hasher.clone();
+    hasher.update(bytes);
+    let mut digest = vec![0; args.length];
+    hasher.finalize_into(&mut digest);
+    match args.multihash {
+      Some(code) => multihash::encode(code, digest),
+      None => digest,
+    }
+  })
+}
+
+#[cfg(feature = "sha3")]
+impl XofHasher for sha3::Shake128 {
+  const DEFAULT_LENGTH: usize = 32;
+  const MULTICODEC: Option<u64> = Some(0x18);
+
+  fn name() -> &'static str {
+    "shake128"
+  }
+
+  fn examples() -> Vec<Example<'static>> {
+    vec![
+      Example {
+        description: "Return the shake128 hash of a string, hex-encoded",
+        example: "'abcdefghijklmnopqrstuvwxyz' | hash shake128",
+        result: Some(Value::string(
+          "961c919c0854576e561320e81514bf3724197d0715e16a364520384ee997f6ef",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return 16 bytes of the shake128 hash of an empty string",
+        example: "'' | hash shake128 --length 16 --binary",
+        result: Some(Value::binary(
+          vec![
+            0x7f, 0x9c, 0x2b, 0xa4, 0xe8, 0x8f, 0x82, 0x7d, 0x61, 0x60, 0x45,
+            0x50, 0x76, 0x05, 0x85, 0x3e,
+          ],
+          Span::test_data(),
+        )),
+      },
+    ]
+  }
+
+  fn from_call(_call: &EvaluatedCall) -> Result<Self, LabeledError> {
+    Ok(Self::default())
+  }
+
+  fn finalize_into(self, output: &mut [u8]) {
+    digest::ExtendableOutput::finalize_xof_into(self, output)
+  }
+}
+
+#[cfg(feature = "sha3")]
+impl XofHasher for sha3::Shake256 {
+  const DEFAULT_LENGTH: usize = 64;
+  const MULTICODEC: Option<u64> = Some(0x19);
+
+  fn name() -> &'static str {
+    "shake256"
+  }
+
+  fn examples() -> Vec<Example<'static>> {
+    vec![
+      Example {
+        description: "Return the shake256 hash of a string, hex-encoded",
+        example: "'abcdefghijklmnopqrstuvwxyz' | hash shake256",
+        result: Some(Value::string(
+          "b7b78b04a3dd30a265c8886c33fda94799853de5d3d10541fd4e9f4613701c61\
+           075249bed16b0781108fcfe086dbf38a7fb8300807cea85cc649328d07d4ff2b",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return 16 bytes of the shake256 hash of an empty string",
+        example: "'' | hash shake256 --length 16",
+        result: Some(Value::string(
+          "46b9dd2b0ba88d13233b3feb743eeb24",
+          Span::test_data(),
+        )),
+      },
+    ]
+  }
+
+  fn from_call(_call: &EvaluatedCall) -> Result<Self, LabeledError> {
+    Ok(Self::default())
+  }
+
+  fn finalize_into(self, output: &mut [u8]) {
+    digest::ExtendableOutput::finalize_xof_into(self, output)
+  }
+}
+
+#[cfg(feature = "sha3")]
+fn cshake_signature(signature: Signature) -> Signature {
+  signature.named(
+    "customization",
+    SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+    "Customization string, empty by default",
+    Some('c'),
+  )
+}
+
+#[cfg(feature = "sha3")]
+impl XofHasher for sha3::CShake128 {
+  const DEFAULT_LENGTH: usize = 32;
+
+  fn name() -> &'static str {
+    "cshake128"
+  }
+
+  fn examples() -> Vec<Example<'static>> {
+    vec![Example {
+      description: "Return the cshake128 hash of binary with a \
+                      customization string (NIST SP 800-185 sample #1)",
+      example: "0x[00010203] | hash cshake128 --customization \
+                  'Email Signature'",
+      result: Some(Value::string(
+        "c1c36925b6409a04f1b504fcbca9d82b4017277cb5ed2b2065fc1d3814d5aaf5",
+        Span::test_data(),
+      )),
+    }]
+  }
+
+  fn signature(signature: Signature) -> Signature {
+    cshake_signature(signature)
+  }
+
+  fn from_call(call: &EvaluatedCall) -> Result<Self, LabeledError> {
+    let customization: Vec<u8> =
+      call.get_flag("customization")?.unwrap_or_default();
+    Ok(Self::from_core(sha3::CShake128Core::new(&customization)))
+  }
+
+  fn finalize_into(self, output: &mut [u8]) {
+    digest::ExtendableOutput::finalize_xof_into(self, output)
+  }
+}
+
+#[cfg(feature = "sha3")]
+impl XofHasher for sha3::CShake256 {
+  const DEFAULT_LENGTH: usize = 64;
+
+  fn name() -> &'static str {
+    "cshake256"
+  }
+
+  fn examples() -> Vec<Example<'static>> {
+    vec![Example {
+      description: "Return the cshake256 hash of binary with a \
+                      customization string (NIST SP 800-185 sample #3)",
+      example: "0x[00010203] | hash cshake256 --customization \
+                  'Email Signature'",
+      result: Some(Value::string(
+        "d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd1\
+           64020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c",
+        Span::test_data(),
+      )),
+    }]
+  }
+
+  fn signature(signature: Signature) -> Signature {
+    cshake_signature(signature)
+  }
+
+  fn from_call(call: &EvaluatedCall) -> Result<Self, LabeledError> {
+    let customization: Vec<u8> =
+      call.get_flag("customization")?.unwrap_or_default();
+    Ok(Self::from_core(sha3::CShake256Core::new(&customization)))
+  }
+
+  fn finalize_into(self, output: &mut [u8]) {
+    digest::ExtendableOutput::finalize_xof_into(self, output)
+  }
+}
+
+/// Size of chunks, that BLAKE3 hashes on several threads. Hashing smaller
+/// inputs in parallel isn't worth it.
+#[cfg(feature = "blake3-rayon")]
+const BLAKE3_RAYON_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Hashes a file with BLAKE3 on several threads, reading it with a memory
+/// map. Returns the number of bytes read and the digest, or `None` if the
+/// file isn't mapped by `hash_mapped`.
+#[cfg(feature = "blake3-rayon")]
+pub fn blake3_file(
+  path: &std::path::Path,
+  signals: &nu_protocol::Signals,
+  span: Span,
+) -> Result<Option<(u64, Vec<u8>)>, ShellError> {
+  let file = std::fs::File::open(path).map_err(|error| {
+    nu_protocol::shell_error::io::IoError::new(error, span, path.to_path_buf())
+  })?;
+  let mut hasher = blake3::Hasher::new();
+  let update = &mut |chunk: &[u8]| {
+    hasher.update_rayon(chunk);
+  };
+  let size =
+    crate::file::hash_mapped(&file, 0, u64::MAX, update, signals, span)?;
+  Ok(size.map(|size| (size, hasher.finalize().as_bytes().to_vec())))
+}
+
+/// Adapts BLAKE3 to `Write`, hashing large writes on several threads.
+#[cfg(feature = "blake3-rayon")]
+struct Blake3RayonWriter<'a>(&'a mut blake3::Hasher);
+
+#[cfg(feature = "blake3-rayon")]
+impl Write for Blake3RayonWriter<'_> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    if buf.len() >= BLAKE3_RAYON_CHUNK_SIZE {
+      self.0.update_rayon(buf);
+    } else {
+      self.0.update(buf);
+    }
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+/// Creates a BLAKE3 hasher in the keyed hash mode, which takes 32-byte keys.
+#[cfg(feature = "blake3")]
+fn blake3_keyed(
+  key: &Spanned<Vec<u8>>,
+) -> Result<blake3::Hasher, LabeledError> {
+  let bytes =
+    <[u8; blake3::KEY_LEN]>::try_from(key.item.as_slice()).map_err(|_| {
+      LabeledError::new("Invalid key length").with_label(
+        format!(
+          "{} bytes long, but must be {} bytes long",
+          key.item.len(),
+          blake3::KEY_LEN
+        ),
+        key.span,
+      )
+    })?;
+  Ok(blake3::Hasher::new_keyed(&bytes))
+}
+
+/// Creates a keyed BLAKE3 hasher for the registry.
+#[cfg(feature = "blake3")]
+pub fn new_keyed_blake3(
+  key: &Spanned<Vec<u8>>,
+) -> Result<Box<dyn crate::hasher::DynHasher>, LabeledError> {
+  Ok(Box::new(blake3_keyed(key)?))
+}
+
+/// BLAKE3 hasher, that starts its output stream from an arbitrary position.
+#[cfg(feature = "blake3")]
+#[derive(Clone)]
+pub struct Blake3 {
+  hasher: blake3::Hasher,
+  seek: u64,
+}
+
+#[cfg(feature = "blake3")]
+impl Update for Blake3 {
+  fn update(&mut self, data: &[u8]) {
+    self.hasher.update(data);
+  }
+}
+
+#[cfg(feature = "blake3")]
+impl Write for Blake3 {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.hasher.write(buf)
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    self.hasher.flush()
+  }
+}
+
+#[cfg(feature = "blake3")]
+impl XofHasher for Blake3 {
+  const DEFAULT_LENGTH: usize = blake3::OUT_LEN;
+  const MULTICODEC: Option<u64> = Some(0x1e);
+
+  fn name() -> &'static str {
+    "blake3"
+  }
+
+  fn examples() -> Vec<Example<'static>> {
+    vec![
+      Example {
+        description: "Return the blake3 hash of a string, hex-encoded",
+        example: "'abcdefghijklmnopqrstuvwxyz' | hash blake3",
+        result: Some(Value::string(
+          "2468eec8894acfb4e4df3a51ea916ba115d48268287754290aae8e9e6228e85f",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return 64 bytes of the blake3 hash of an empty string \
+                      (official test vector)",
+        example: "'' | hash blake3 --length 64",
+        result: Some(Value::string(
+          "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262\
+           e00f03e7b69af26b7faaf09fcd333050338ddfe085b8cc869ca98b206c08243a",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return 16 bytes of the blake3 hash of an empty string, \
+                      starting from 16th byte of the output stream",
+        example: "'' | hash blake3 --length 16 --seek 16",
+        result: Some(Value::string(
+          "9bcb25c9adc112b7cc9a93cae41f3262",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the keyed blake3 hash of an empty string \
+                      (official test vector)",
+        example: "'' | hash blake3 --key 'whats the Elvish word for friend'",
+        result: Some(Value::string(
+          "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Derive a key from an empty string (official test \
+                      vector)",
+        example: "'' | hash blake3 --derive-key \
+                  'BLAKE3 2019-12-27 16:29:52 test vectors context'",
+        result: Some(Value::string(
+          "2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d",
+          Span::test_data(),
+        )),
+      },
+      Example {
+        description: "Return the blake3 hash of a file's contents",
+        example: "open ./nu_0_24_1_windows.zip | hash blake3",
+        result: None,
+      },
+    ]
+  }
+
+  fn signature(signature: Signature) -> Signature {
+    signature
+      .named(
+        "seek",
+        SyntaxShape::Int,
+        "Skip this many bytes of the output stream",
+        Some('s'),
+      )
+      .named(
+        "key",
+        SyntaxShape::OneOf(vec![SyntaxShape::Binary, SyntaxShape::String]),
+        "Key of the keyed hash mode, exactly 32 bytes long",
+        Some('k'),
+      )
+      .named(
+        "derive-key",
+        SyntaxShape::String,
+        "Context string of the key derivation mode",
+        Some('d'),
+      )
+  }
+
+  fn from_call(call: &EvaluatedCall) -> Result<Self, LabeledError> {
+    let multihash =
+      call.get_flag::<String>("format")?.as_deref() == Some("multihash");
+    let seek = match call.get_flag::<Spanned<i64>>("seek")? {
+      // Later parts of the output stream aren't BLAKE3 hashes, so they have
+      // no code
+      Some(seek) if multihash && seek.item != 0 => {
+        return Err(
+          LabeledError::new("Incompatible flags")
+            .with_label("seeked outputs have no multihash code", seek.span)
+            .with_help("remove --seek or --format"),
+        )
+      }
+      Some(seek) => u64::try_from(seek.item).map_err(|_| {
+        LabeledError::new("Invalid seek position")
+          .with_label("seek position can't be negative", seek.span)
+      })?,
+      None => 0,
+    };
+    let key = call.get_flag::<Spanned<Vec<u8>>>("key")?;
+    let context = call.get_flag::<Spanned<String>>("derive-key")?;
+    let hasher = match (key, context) {
+      (Some(_), Some(context)) => {
+        return Err(
+          LabeledError::new("Incompatible flags")
+            .with_label("key derivation can't be keyed", context.span)
+            .with_help("remove --key or --derive-key"),
+        )
+      }
+      (Some(key), None) => blake3_keyed(&key)?,
+      (None, Some(context)) => {
+        // Derived keys aren't plain BLAKE3 hashes, so they have no code
+        if multihash {
+          return Err(
+            LabeledError::new("Incompatible flags")
+              .with_label("derived keys have no multihash code", context.span)
+              .with_help("remove --derive-key or --format"),
+          );
+        }
+        blake3::Hasher::new_derive_key(&context.item)
+      }
+      (None, None) => blake3::Hasher::new(),
+    };
+    Ok(Self { hasher, seek })
+  }
+
+  #[cfg(feature = "blake3-rayon")]
+  fn update_stream(
+    &mut self,
+    stream: ByteStream,
+    span: Span,
+  ) -> Result<(), ShellError> {
+    // Streams are written in small chunks, they are gathered into chunks
+    // large enough to be hashed in parallel
+    let mut writer = std::io::BufWriter::with_capacity(
+      BLAKE3_RAYON_CHUNK_SIZE,
+      Blake3RayonWriter(&mut self.hasher),
+    );
+    stream.write_to(&mut writer)?;
+    writer.flush().map_err(|error| {
+      nu_protocol::shell_error::io::IoError::new(error, span, None).into()
+    })
+  }
+
+  fn finalize_into(self, output: &mut [u8]) {
+    let mut reader = self.hasher.finalize_xof();
+    reader.set_position(self.seek);
+    reader.fill(output);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nu_plugin_test_support::PluginTest;
+  use nu_protocol::ShellError;
+
+  use super::*;
+
+  fn test_examples<H>() -> Result<(), ShellError>
+  where
+    H: XofHasher + Write + Send + Sync + 'static,
+  {
+    let command = GenericXofHasher::<H>::default();
+    PluginTest::new(&command.name, HashesPlugin.into())?
+      .test_examples(&command.examples())
+  }
+
+  #[cfg(feature = "sha3")]
+  #[test]
+  fn test_shake128_examples() -> Result<(), ShellError> {
+    test_examples::<sha3::Shake128>()
+  }
+
+  #[cfg(feature = "sha3")]
+  #[test]
+  fn test_shake256_examples() -> Result<(), ShellError> {
+    test_examples::<sha3::Shake256>()
+  }
+
+  #[cfg(feature = "sha3")]
+  #[test]
+  fn test_cshake128_examples() -> Result<(), ShellError> {
+    test_examples::<sha3::CShake128>()
+  }
+
+  #[cfg(feature = "sha3")]
+  #[test]
+  fn test_cshake256_examples() -> Result<(), ShellError> {
+    test_examples::<sha3::CShake256>()
+  }
+
+  #[cfg(feature = "blake3")]
+  #[test]
+  fn test_blake3_examples() -> Result<(), ShellError> {
+    test_examples::<Blake3>()
+  }
+
+  #[cfg(feature = "blake3")]
+  #[test]
+  fn test_blake3_modes() -> Result<(), ShellError> {
+    use nu_protocol::record;
+
+    let key = "whats the Elvish word for friend";
+    let mut plugin_test = PluginTest::new("hash blake3", HashesPlugin.into())?;
+    let mut hash = |command: &str| {
+      plugin_test
+        .eval(command)
+        .and_then(|data| data.into_value(Span::test_data()))
+    };
+    assert_eq!(
+      hash(&format!("{{a: ''}} | hash blake3 --key '{key}' a"))?,
+      Value::test_record(record! {
+        "a" => Value::test_string(
+          "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26"
+        ),
+      })
+    );
+    assert!(hash("'' | hash blake3 --key 'too short'").is_err());
+    assert!(hash(&format!("'' | hash blake3 --key '{key}' -d ctx")).is_err());
+    assert!(hash("'' | hash blake3 -d ctx --format multihash").is_err());
+    assert!(hash("'' | hash blake3 --seek 1 --format multihash").is_err());
+    assert!(hash("'' | hash blake3 --seek 0 --format multihash").is_ok());
+    assert!(hash("'' | hash blake3 --length 0").is_err());
+    assert!(
+      hash(&format!("'' | hash blake3 --length {}", MAX_LENGTH + 1)).is_err()
+    );
+    assert!(hash("'' | hash blake3 --length 9223372036854775807").is_err());
+    Ok(())
+  }
+
+  #[cfg(feature = "blake3-rayon")]
+  #[test]
+  fn test_blake3_rayon() -> Result<(), ShellError> {
+    use nu_protocol::Signals;
+
+    // Several chunks and a remainder, that is hashed on a single thread
+    let data: Vec<u8> = (0..3 * BLAKE3_RAYON_CHUNK_SIZE + 7)
+      .map(|i| i as u8)
+      .collect();
+    let expected = blake3::hash(&data);
+
+    let stream = ByteStream::read_binary(
+      data.clone(),
+      Span::test_data(),
+      Signals::empty(),
+    );
+    let digest = PluginTest::new("hash blake3", HashesPlugin.into())?
+      .eval_with("hash blake3", PipelineData::byte_stream(stream, None))?
+      .into_value(Span::test_data())?;
+    assert_eq!(digest.into_string()?, expected.to_hex().as_str());
+
+    let dir = crate::file::TestDir::new("blake3_rayon");
+    let path = dir.join("data.bin");
+    std::fs::write(&path, &data).unwrap();
+    let (size, digest) =
+      blake3_file(&path, &Signals::empty(), Span::test_data())?.unwrap();
+    assert_eq!(size, data.len() as u64);
+    assert_eq!(digest, expected.as_bytes());
+    Ok(())
+  }
+
+  /// Rows of `hash algorithms` are generated from the list in the build
+  /// script, check them against the commands.
+  #[cfg(feature = "sha3")]
+  #[test]
+  fn test_algorithm_rows() {
+    use digest::core_api::BlockSizeUser;
+
+    fn check<H: XofHasher + BlockSizeUser>() {
+      let row = crate::registry::COMMAND_ALGORITHMS
+        .iter()
+        .find(|row| row.name == H::name())
+        .unwrap();
+      assert_eq!(row.output_size, H::DEFAULT_LENGTH, "{}", H::name());
+      assert_eq!(row.block_size, H::block_size(), "{}", H::name());
+      assert_eq!(row.multicodec, H::MULTICODEC, "{}", H::name());
+    }
+    check::<sha3::Shake128>();
+    check::<sha3::Shake256>();
+    check::<sha3::CShake128>();
+    check::<sha3::CShake256>();
+  }
+}